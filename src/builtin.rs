@@ -1,4 +1,12 @@
-use crate::{Module, Result, Value, symbol, try_as_array};
+use anyhow::anyhow;
+use num_complex::Complex64;
+
+use crate::{
+    Expr, Module, Pretty, Result, ResultIterator, Value, egraph,
+    error::expected,
+    logic, parser, rewrite, symbol, symbolic, try_as_array,
+    value::{Compound, Map, Set},
+};
 
 fn type_(values: &[Value]) -> Result<Value> {
     let [value] = try_as_array(values)?;
@@ -10,6 +18,471 @@ fn list(values: &[Value]) -> Result<Value> {
     Ok(Value::list(values))
 }
 
+fn eqv(values: &[Value]) -> Result<Value> {
+    let [a, b] = try_as_array(values)?;
+    Ok(a.eqv(b).into())
+}
+
+fn equal(values: &[Value]) -> Result<Value> {
+    let [a, b] = try_as_array(values)?;
+    Ok((a == b).into())
+}
+
+fn compare(values: &[Value]) -> Result<Value> {
+    let [a, b] = try_as_array(values)?;
+    Ok(Value::from(a.cmp(b) as i64))
+}
+
+fn to_vector(value: &Value) -> Result<Value> {
+    match value {
+        Value::Vector(_) => Ok(value.clone()),
+        _ if value.is_proper_list() => Ok(value.iter().cloned().collect::<im::Vector<_>>().into()),
+        _ => Err(expected("list or vector", value)),
+    }
+}
+
+fn index(value: &Value, len: usize) -> Result<usize> {
+    let i = value.as_integer()?;
+    usize::try_from(i)
+        .ok()
+        .filter(|&i| i <= len)
+        .ok_or_else(|| anyhow!("index {i} out of bounds for length {len}"))
+}
+
+fn vec(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    to_vector(value)
+}
+
+fn nth(values: &[Value]) -> Result<Value> {
+    let [coll, i] = try_as_array(values)?;
+    let items = to_vector(coll)?.as_vector()?;
+    let len = items.len();
+    let i = index(i, len)?;
+    items
+        .get(i)
+        .cloned()
+        .ok_or_else(|| anyhow!("index {i} out of bounds for length {len}"))
+}
+
+fn assoc(values: &[Value]) -> Result<Value> {
+    let (coll, pairs) = values
+        .split_first()
+        .ok_or_else(|| anyhow!("expected 3 or more arguments, got 0"))?;
+    if !pairs.len().is_multiple_of(2) {
+        return Err(anyhow!(
+            "expected an even number of keys and values, got {}",
+            pairs.len()
+        ));
+    }
+
+    if let Value::Vector(coll) = coll {
+        let mut coll = (**coll).clone();
+        for pair in pairs.chunks_exact(2) {
+            let i = index(&pair[0], coll.len())?;
+            if i == coll.len() {
+                coll.push_back(pair[1].clone());
+            } else {
+                coll.set(i, pair[1].clone());
+            }
+        }
+        Ok(coll.into())
+    } else {
+        let mut coll = entries(coll)?;
+        for pair in pairs.chunks_exact(2) {
+            coll.insert(pair[0].clone(), pair[1].clone());
+        }
+        Ok(coll.into())
+    }
+}
+
+fn entries(value: &Value) -> Result<Map> {
+    match value {
+        Value::Map(entries) => Ok((**entries).clone()),
+        _ if value.is_nil() => Ok(Map::default()),
+        _ => Err(expected("map", value)),
+    }
+}
+
+fn get(values: &[Value]) -> Result<Value> {
+    let (coll, key, default) = match values {
+        [coll, key] => (coll, key, Value::nil()),
+        [coll, key, default] => (coll, key, default.clone()),
+        _ => return Err(anyhow!("expected 2 or 3 arguments, got {}", values.len())),
+    };
+    let value = match coll {
+        Value::Map(entries) => entries.get(key).cloned(),
+        Value::Set(values) => values.contains(key).then(|| key.clone()),
+        Value::Vector(values) => usize::try_from(key.as_integer()?)
+            .ok()
+            .and_then(|i| values.get(i).cloned()),
+        _ if coll.is_nil() => None,
+        _ => return Err(expected("map, set or vector", coll)),
+    };
+    Ok(value.unwrap_or(default))
+}
+
+fn dissoc(values: &[Value]) -> Result<Value> {
+    let (coll, keys) = values
+        .split_first()
+        .ok_or_else(|| anyhow!("expected 1 or more arguments, got 0"))?;
+    let mut coll = entries(coll)?;
+    for key in keys {
+        coll.remove(key);
+    }
+    Ok(coll.into())
+}
+
+fn keys(values: &[Value]) -> Result<Value> {
+    let [coll] = try_as_array(values)?;
+    let keys: Vec<_> = coll
+        .as_map()?
+        .sorted()
+        .into_iter()
+        .map(|(key, _)| key.clone())
+        .collect();
+    Ok(Value::list(keys))
+}
+
+fn vals(values: &[Value]) -> Result<Value> {
+    let [coll] = try_as_array(values)?;
+    let vals: Vec<_> = coll
+        .as_map()?
+        .sorted()
+        .into_iter()
+        .map(|(_, value)| value.clone())
+        .collect();
+    Ok(Value::list(vals))
+}
+
+fn merge(values: &[Value]) -> Result<Value> {
+    let mut merged = im::HashMap::new();
+    for value in values.iter().filter(|value| !value.is_nil()) {
+        let entries = value.as_map()?;
+        merged.extend(entries.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+    Ok(merged.into())
+}
+
+fn contains(values: &[Value]) -> Result<Value> {
+    let [coll, key] = try_as_array(values)?;
+    let contains = match coll {
+        Value::Map(entries) => entries.contains_key(key),
+        Value::Set(values) => values.contains(key),
+        Value::Vector(values) => usize::try_from(key.as_integer()?).is_ok_and(|i| i < values.len()),
+        _ if coll.is_nil() => false,
+        _ => return Err(expected("map, set or vector", coll)),
+    };
+    Ok(contains.into())
+}
+
+// The result shares nodes with the first set, so each op changes it in place.
+fn set_op(values: &[Value], op: fn(&mut Set, &Set)) -> Result<Value> {
+    let (first, rest) = values
+        .split_first()
+        .ok_or_else(|| anyhow!("expected 1 or more arguments, got 0"))?;
+    let mut result = (*first.as_set()?).clone();
+    for value in rest {
+        op(&mut result, &*value.as_set()?);
+    }
+    Ok(result.into())
+}
+
+fn union(values: &[Value]) -> Result<Value> {
+    set_op(values, |result, other| result.extend(other.iter().cloned()))
+}
+
+fn intersection(values: &[Value]) -> Result<Value> {
+    set_op(values, |result, other| {
+        result.retain(|value| other.contains(value));
+    })
+}
+
+fn difference(values: &[Value]) -> Result<Value> {
+    set_op(values, |result, other| {
+        for value in other.iter() {
+            result.remove(value);
+        }
+    })
+}
+
+// Conjugates a single number, or adds values to a collection.
+fn conj(values: &[Value]) -> Result<Value> {
+    if let [value] = values
+        && value.is_number()
+    {
+        return Ok(conjugate(value));
+    }
+
+    let (coll, values) = values
+        .split_first()
+        .ok_or_else(|| anyhow!("expected 1 or more arguments, got 0"))?;
+    match coll {
+        Value::Vector(coll) => {
+            let mut coll = (**coll).clone();
+            coll.extend(values.iter().cloned());
+            Ok(coll.into())
+        }
+        Value::Set(coll) => {
+            let mut coll = (**coll).clone();
+            coll.extend(values.iter().cloned());
+            Ok(coll.into())
+        }
+        _ if coll.is_proper_list() => Ok(values
+            .iter()
+            .fold(coll.clone(), |list, value| Value::cons(value.clone(), list))),
+        _ => Err(expected("list, vector or set", coll)),
+    }
+}
+
+fn count_of(value: &Value) -> Result<usize> {
+    match value {
+        Value::Vector(values) => Ok(values.len()),
+        Value::Bytes(bytes) => Ok(bytes.len()),
+        Value::Map(entries) => Ok(entries.len()),
+        Value::Set(values) => Ok(values.len()),
+        Value::String(s) => Ok(s.chars().count()),
+        _ if value.is_proper_list() => Ok(value.iter().count()),
+        _ => Err(expected("collection", value)),
+    }
+}
+
+fn count(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    let len = count_of(value)?;
+    Ok(Value::from(i64::try_from(len)?))
+}
+
+fn subvec(values: &[Value]) -> Result<Value> {
+    let (coll, start, end) = match values {
+        [coll, start] => (coll, start, None),
+        [coll, start, end] => (coll, start, Some(end)),
+        _ => return Err(anyhow!("expected 2 or 3 arguments, got {}", values.len())),
+    };
+    let mut values = (*coll.as_vector()?).clone();
+    let len = values.len();
+    let end = end.map_or(Ok(len), |end| index(end, len))?;
+    let start = index(start, end)?;
+    values.truncate(end);
+    *values = values.skip(start);
+    Ok(values.into())
+}
+
+fn compound(values: &[Value]) -> Result<Value> {
+    let (type_, args) = values
+        .split_first()
+        .ok_or_else(|| anyhow!("expected 1 or more arguments, got 0"))?;
+    let type_ = type_.as_symbol()?;
+    Compound::check_arity(type_, args.len())?;
+    Ok(Value::compound(type_, args.to_vec()))
+}
+
+fn compound_type(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    Ok(value.as_compound()?.type_.into())
+}
+
+fn compound_args(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    Ok(Value::list(&value.as_compound()?.values))
+}
+
+fn char_to_integer(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    Ok(i64::from(u32::from(value.as_char()?)).into())
+}
+
+fn integer_to_char(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    let code = value.as_integer()?;
+    u32::try_from(code)
+        .ok()
+        .and_then(char::from_u32)
+        .map(Value::from)
+        .ok_or_else(|| anyhow!("invalid character code {code}"))
+}
+
+fn char_to_string(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    Ok(value.as_char()?.to_string().into())
+}
+
+fn string_to_chars(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    let chars: im::Vector<_> = value.as_string()?.chars().map(Value::from).collect();
+    Ok(chars.into())
+}
+
+fn chars_to_string(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    let s = to_vector(value)?
+        .as_vector()?
+        .iter()
+        .map(Value::as_char)
+        .collect::<Result<String>>()?;
+    Ok(s.into())
+}
+
+fn keyword(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    match value {
+        Value::Keyword(_) => Ok(value.clone()),
+        &Value::Symbol(sym) => Ok(Value::Keyword(sym)),
+        Value::String(s) => Ok(Value::keyword(&**s)),
+        _ => Err(expected("keyword, symbol or string", value)),
+    }
+}
+
+fn keyword_to_string(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    Ok(value.as_keyword()?.as_str().to_string().into())
+}
+
+fn string_to_keyword(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    Ok(Value::keyword(&*value.as_string()?))
+}
+
+// `(pretty value [width])` lays `value` out as a string of lines at most `width` wide.
+fn pretty(values: &[Value]) -> Result<Value> {
+    let (value, width) = match values {
+        [value] => (value, 80),
+        [value, width] => (value, usize::try_from(width.as_integer()?)?),
+        _ => return Err(anyhow!("expected 1 or 2 arguments, got {}", values.len())),
+    };
+    Ok(Pretty::new().width(width).render(value).into())
+}
+
+fn bytes(values: &[Value]) -> Result<Value> {
+    let bytes: Vec<u8> = values
+        .iter()
+        .map(|value| {
+            let byte = value.as_integer()?;
+            u8::try_from(byte).map_err(|_| anyhow!("byte {byte} is out of range"))
+        })
+        .try_collect()?;
+    Ok(bytes.into())
+}
+
+fn string_to_bytes(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    Ok(value.as_string()?.as_bytes().to_vec().into())
+}
+
+fn bytes_to_string(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    let s = String::from_utf8(value.as_bytes()?.0.clone())
+        .map_err(|err| anyhow!("invalid UTF-8 in {value}: {err}"))?;
+    Ok(s.into())
+}
+
+fn box_(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    Ok(Value::boxed(value.clone()))
+}
+
+fn unbox(values: &[Value]) -> Result<Value> {
+    let [cell] = try_as_array(values)?;
+    Ok(cell.as_box()?.get())
+}
+
+fn set_box(values: &[Value]) -> Result<Value> {
+    let [cell, value] = try_as_array(values)?;
+    cell.as_box()?.set(value.clone());
+    Ok(value.clone())
+}
+
+fn numerator(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    value
+        .numerator()
+        .map(Value::from)
+        .ok_or_else(|| expected("exact number", value))
+}
+
+fn denominator(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    value
+        .denominator()
+        .map(Value::from)
+        .ok_or_else(|| expected("exact number", value))
+}
+
+fn exact_to_inexact(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    Ok(value.as_number()?.into())
+}
+
+fn as_complex(value: &Value) -> Result<Complex64> {
+    value.to_complex().ok_or_else(|| expected("number", value))
+}
+
+fn re(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    match value {
+        Value::Complex(num) => Ok(num.re.into()),
+        _ if value.is_number() => Ok(value.clone()),
+        _ => Err(expected("number", value)),
+    }
+}
+
+fn im(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    match value {
+        Value::Complex(num) => Ok(num.im.into()),
+        _ if value.is_number() => Ok(0.into()),
+        _ => Err(expected("number", value)),
+    }
+}
+
+fn conjugate(value: &Value) -> Value {
+    match value {
+        Value::Complex(num) => num.conj().into(),
+        _ => value.clone(),
+    }
+}
+
+fn arg(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    Ok(as_complex(value)?.arg().into())
+}
+
+fn magnitude(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    Ok(as_complex(value)?.norm().into())
+}
+
+fn exp(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    match value {
+        Value::Complex(num) => Ok(num.exp().into()),
+        _ => Ok(value.as_number()?.exp().into()),
+    }
+}
+
+fn log(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    let num = as_complex(value)?;
+    if value.is_complex() || num.re < 0.0 {
+        Ok(num.ln().into())
+    } else {
+        Ok(num.re.ln().into())
+    }
+}
+
+// Natives can't call closures, so `swap!` is written in jymbol over `unbox` and `set-box!`.
+const SWAP: &str = "(fn [b f] (set-box! b (f (unbox b))))";
+
+fn define_swap(module: &mut Module) {
+    let value = parser::parse(SWAP, parser::value()).expect("swap! should parse");
+    let expr = Expr::try_from(&value).expect("swap! should compile");
+    let swap = module
+        .vm
+        .eval_builtin(&module.env, &expr)
+        .expect("swap! should evaluate");
+    module.set("swap!", swap);
+}
+
 pub fn define_all(module: &mut Module) {
     module.set(*symbol::NIL, Value::nil());
     module.set(*symbol::TRUE, Value::true_());
@@ -17,4 +490,288 @@ pub fn define_all(module: &mut Module) {
 
     module.set_native("type", type_, 1);
     module.set_native("list", list, ..);
+    module.set_native("eqv?", eqv, 2);
+    module.set_native("equal?", equal, 2);
+    module.set_native("compare", compare, 2);
+    module.set_native("vec", vec, 1);
+    module.set_native("nth", nth, 2);
+    module.set_native("assoc", assoc, 3..);
+    module.set_native("conj", conj, 1..);
+    module.set_native("count", count, 1);
+    module.set_native("subvec", subvec, 2..);
+    module.set_native("get", get, 2..);
+    module.set_native("dissoc", dissoc, 1..);
+    module.set_native("keys", keys, 1);
+    module.set_native("vals", vals, 1);
+    module.set_native("merge", merge, ..);
+    module.set_native("contains?", contains, 2);
+    module.set_native("union", union, 1..);
+    module.set_native("intersection", intersection, 1..);
+    module.set_native("difference", difference, 1..);
+    module.set_native("compound", compound, 1..);
+    module.set_native("compound-type", compound_type, 1);
+    module.set_native("compound-args", compound_args, 1);
+    module.set_native("char->integer", char_to_integer, 1);
+    module.set_native("integer->char", integer_to_char, 1);
+    module.set_native("char->string", char_to_string, 1);
+    module.set_native("string->chars", string_to_chars, 1);
+    module.set_native("chars->string", chars_to_string, 1);
+    module.set_native("keyword", keyword, 1);
+    module.set_native("keyword->string", keyword_to_string, 1);
+    module.set_native("string->keyword", string_to_keyword, 1);
+    module.set_native("pretty", pretty, 1..);
+    module.set_native("bytes", bytes, ..);
+    module.set_native("string->bytes", string_to_bytes, 1);
+    module.set_native("bytes->string", bytes_to_string, 1);
+    module.set_native("box", box_, 1);
+    module.set_native("unbox", unbox, 1);
+    module.set_native("set-box!", set_box, 2);
+    module.set_native("numerator", numerator, 1);
+    module.set_native("denominator", denominator, 1);
+    module.set_native("exact->inexact", exact_to_inexact, 1);
+    define_swap(module);
+    module.set_native("re", re, 1);
+    module.set_native("im", im, 1);
+    module.set_native("arg", arg, 1);
+    module.set_native("magnitude", magnitude, 1);
+    module.set_native("exp", exp, 1);
+    module.set_native("log", log, 1);
+    rewrite::define_all(module);
+    logic::define_all(module);
+    symbolic::define_all(module);
+    egraph::define_all(module);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Module, VM, Value};
+
+    use super::assoc;
+
+    #[test]
+    fn numbers() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+        let mut eval = |s: &str| module.eval_str(s).unwrap().to_string();
+
+        assert_eq!("1", eval("(numerator ($div 2 6))"));
+        assert_eq!("3", eval("(denominator ($div 2 6))"));
+        assert_eq!("1", eval("(denominator 7)"));
+        assert_eq!("0.25", eval("(exact->inexact 1/4)"));
+        assert_eq!(
+            "15511210043330985984000000",
+            eval("(loop [n 25 acc 1] (if ($eq n 0) acc (recur ($sub n 1) ($mul acc n))))")
+        );
+        assert_eq!(Value::from(1), module.eval_str("($add 1/3 2/3)").unwrap());
+    }
+
+    #[test]
+    fn equality() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+        let mut eval = |s: &str| module.eval_str(s).unwrap().to_string();
+
+        assert_eq!("true", eval("(equal? '(1 \"a\") '(1 \"a\"))"));
+        assert_eq!("false", eval("(eqv? '(1 \"a\") '(1 \"a\"))"));
+        assert_eq!("true", eval("(let x '(1 2) (eqv? x x))"));
+        assert_eq!(
+            "true",
+            eval("(eqv? 100000000000000000000 100000000000000000000)")
+        );
+        assert_eq!("false", eval("(equal? 1 1.0)"));
+        assert_eq!("true", eval("($eq 1 1.0)"));
+        assert_eq!("-1", eval("(compare 1 1.0)"));
+        assert_eq!("-1", eval("(compare 2 'a)"));
+        assert_eq!("1", eval("(compare \"b\" \"a\")"));
+        assert_eq!("true", eval("($lt 'a \"a\")"));
+    }
+
+    #[test]
+    fn vectors() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+        let mut eval = |s: &str| module.eval_str(s).unwrap().to_string();
+
+        assert_eq!("[1 2 3]", eval("(let x 1 [x ($add x 1) 3])"));
+        assert_eq!("[a (b)]", eval("'[a (b)]"));
+        assert_eq!("[1 2]", eval("(vec '(1 2))"));
+        assert_eq!("2", eval("(nth [1 2 3] 1)"));
+        assert_eq!("3", eval("(nth '(1 2 3) 2)"));
+        assert_eq!(
+            "([1 2 3] [1 x 3] [1 2 3 4])",
+            eval("(let v [1 2 3] (list v (assoc v 1 'x) (assoc v 3 4)))")
+        );
+        assert_eq!("[1 2 3 4]", eval("(conj [1 2] 3 4)"));
+        assert_eq!("(4 3 1 2)", eval("(conj '(1 2) 3 4)"));
+        assert_eq!("3", eval("(count [1 2 3])"));
+        assert_eq!("[2 3]", eval("(subvec [1 2 3 4] 1 3)"));
+        assert_eq!("[3 4]", eval("(subvec [1 2 3 4] 2)"));
+        assert_eq!("vector", eval("(type [])"));
+        assert_eq!("6", eval("((fn [a b] ($mul a b)) 2 3)"));
+
+        assert!(module.eval_str("(nth [1 2] 2)").is_err());
+        assert!(module.eval_str("(subvec [1 2] 2 1)").is_err());
+
+        let mut error = |s: &str| module.eval_str(s).unwrap_err().to_string();
+        let dotted = "expected list or vector, got (1 . 2)";
+        assert_eq!(dotted, error("(vec (compound 'cons 1 2))"));
+        assert_eq!(dotted, error("(nth '(1 . 2) 0)"));
+        assert_eq!(
+            "expected list or vector, got (#\\a . #\\b)",
+            error("(chars->string (compound 'cons #\\a #\\b))")
+        );
+        assert_eq!(
+            "expected collection, got (1 . 2)",
+            error("(count '(1 . 2))")
+        );
+        assert_eq!(
+            "expected list, vector or set, got (1 . 2)",
+            error("(conj '(1 . 2) 3)")
+        );
+    }
+
+    #[test]
+    fn shared_nodes() {
+        let values: im::Vector<Value> = (0..1000).map(|i| Value::from(i.to_string())).collect();
+        let vector = Value::from(values);
+        let updated = assoc(&[vector.clone(), 0.into(), Value::nil()]).unwrap();
+
+        // The last string is in a node both vectors share, rather than in a copy of it.
+        let Value::String(last) = &updated.as_vector().unwrap()[999] else {
+            unreachable!()
+        };
+        assert_eq!(1, last.ref_count().get());
+        assert_eq!("nil", updated.as_vector().unwrap()[0].to_string());
+        assert_eq!("\"0\"", vector.as_vector().unwrap()[0].to_string());
+    }
+
+    #[test]
+    fn maps_and_sets() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+        let mut eval = |s: &str| module.eval_str(s).unwrap().to_string();
+
+        assert_eq!("{a 1 b 2}", eval("(let x 1 {'a x 'b ($add x 1)})"));
+        assert_eq!("#{1 2 3}", eval("#{3 1 2 1}"));
+        assert_eq!("2", eval("(get {'a 1 'b 2} 'b)"));
+        assert_eq!("nil", eval("(get {'a 1} 'c)"));
+        assert_eq!("0", eval("(get {'a 1} 'c 0)"));
+        assert_eq!("{a 1 b 3 c 4}", eval("(assoc {'a 1 'b 2} 'b 3 'c 4)"));
+        assert_eq!("{b 2}", eval("(dissoc {'a 1 'b 2} 'a)"));
+        assert_eq!("(a b)", eval("(keys {'b 2 'a 1})"));
+        assert_eq!("(1 2)", eval("(vals {'b 2 'a 1})"));
+        assert_eq!("{a 3 b 2 c 4}", eval("(merge {'a 1 'b 2} {'a 3} {'c 4})"));
+        assert_eq!("true", eval("(contains? {'a 1} 'a)"));
+        assert_eq!("false", eval("(contains? #{1 2} 3)"));
+        assert_eq!("#{1 2 3}", eval("(union #{1 2} #{2 3})"));
+        assert_eq!("#{2}", eval("(intersection #{1 2} #{2 3})"));
+        assert_eq!("#{1}", eval("(difference #{1 2} #{2 3})"));
+        assert_eq!("#{1 2}", eval("(conj #{1} 2)"));
+        assert_eq!("2", eval("(count {'a 1 'b 2})"));
+        assert_eq!("true", eval("(equal? {[1 2] #{'x}} {[1 2] #{'x}})"));
+        assert_eq!("map", eval("(type {})"));
+
+        assert!(module.eval_str("(assoc {} 'a)").is_err());
+        assert!(module.eval_str("(conj 5 1)").is_err());
+    }
+
+    #[test]
+    fn compounds() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+        let mut eval = |s: &str| module.eval_str(s).unwrap().to_string();
+
+        assert_eq!("(#point 1 2)", eval("(compound 'point 1 2)"));
+        assert_eq!("(#point 1 3)", eval("(let y 3 #point(1 y))"));
+        assert_eq!("(#point x y)", eval("'(#point x y)"));
+        assert_eq!("point", eval("(compound-type '#point(1 2))"));
+        assert_eq!("(1 2)", eval("(compound-args '#point(1 2))"));
+        assert_eq!("true", eval("(equal? (compound 'point 1 2) #point(1 2))"));
+        assert_eq!("(1 . 2)", eval("(compound 'cons 1 2)"));
+
+        assert!(module.eval_str("(compound 'cons 1)").is_err());
+        assert!(module.eval_str("(compound 'quote)").is_err());
+    }
+
+    #[test]
+    fn chars_keywords_and_bytes() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+        let mut eval = |s: &str| module.eval_str(s).unwrap().to_string();
+
+        assert_eq!(":a", eval(":a"));
+        assert_eq!("{:x 1}", eval("{:x 1}"));
+        assert_eq!("1", eval("(get {:x 1} :x)"));
+        assert_eq!("keyword", eval("(type :a)"));
+        assert_eq!(":b", eval("(keyword 'b)"));
+        assert_eq!("\"b\"", eval("(keyword->string :b)"));
+        assert_eq!(":c", eval("(string->keyword \"c\")"));
+
+        assert_eq!("char", eval("(type #\\a)"));
+        assert_eq!("97", eval("(char->integer #\\a)"));
+        assert_eq!("#\\space", eval("(integer->char 32)"));
+        assert_eq!("\"a\"", eval("(char->string #\\a)"));
+        assert_eq!("[#\\h #\\i]", eval("(string->chars \"hi\")"));
+        assert_eq!("\"hi\"", eval("(chars->string [#\\h #\\i])"));
+        assert_eq!(
+            "\"(fn [x]\\n  (+ x 1))\"",
+            eval("(pretty '(fn [x] (+ x 1)) 10)")
+        );
+
+        assert_eq!("bytes", eval("(type #b\"\")"));
+        assert_eq!("#b\"AB\\0\"", eval("(bytes 65 66 0)"));
+        assert_eq!("#b\"\\xc3\\xa9\"", eval("(string->bytes \"\u{e9}\")"));
+        assert_eq!("\"\u{e9}\"", eval("(bytes->string #b\"\\xc3\\xa9\")"));
+        assert_eq!("3", eval("(count #b\"a\\nb\")"));
+
+        assert!(module.eval_str("(bytes 256)").is_err());
+        assert!(module.eval_str("(bytes->string #b\"\\xff\")").is_err());
+    }
+
+    #[test]
+    fn boxes() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+        let mut eval = |s: &str| module.eval_str(s).unwrap().to_string();
+
+        assert_eq!("1", eval("(unbox (box 1))"));
+        assert_eq!(
+            "(2 2)",
+            eval("(let b (box 1) (list (set-box! b 2) (unbox b)))")
+        );
+        assert_eq!(
+            "(3 3)",
+            eval(
+                "(let b (box 1) a (swap! b (fn [n] ($add n 1))) (list (swap! b (fn [n] ($add n 1))) (unbox b)))"
+            )
+        );
+        assert_eq!(
+            "(true false)",
+            eval("(let b (box 1) (list (equal? b b) (equal? b (box 1))))")
+        );
+        assert_eq!("box", eval("(type (box nil))"));
+    }
+
+    #[test]
+    fn complex() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+        let mut eval = |s: &str| module.eval_str(s).unwrap().to_string();
+
+        assert_eq!("3.0", eval("(re 3+4i)"));
+        assert_eq!("4.0", eval("(im 3+4i)"));
+        assert_eq!("0", eval("(im 1/2)"));
+        assert_eq!("3.0-4.0i", eval("(conj 3+4i)"));
+        assert_eq!("5", eval("(conj 5)"));
+        assert_eq!("5.0", eval("(magnitude 3+4i)"));
+        assert_eq!("5.0", eval("(magnitude -5)"));
+        assert_eq!("3.141592653589793", eval("(arg -1)"));
+        assert_eq!("0.0", eval("(log 1)"));
+        assert_eq!("0.0+3.141592653589793i", eval("(log -1)"));
+        assert_eq!("1.0", eval("(exp 0)"));
+        assert_eq!("4.0+6.0i", eval("($add 1+2i 3+4i)"));
+        assert_eq!("-5.0+10.0i", eval("($mul 1+2i 3+4i)"));
+        assert_eq!("0.0+2.0i", eval("($sqrt -4)"));
+        assert_eq!("true", eval("($eq 2+0i 2)"));
+    }
 }
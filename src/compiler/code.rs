@@ -18,6 +18,10 @@ impl Code {
         pc
     }
 
+    pub fn last(&self) -> Option<&Inst> {
+        self.0.last()
+    }
+
     pub fn bookmark(&mut self) -> u32 {
         self.emit(Inst::Nop)
     }
@@ -1,8 +1,32 @@
+use std::{collections::HashMap, rc::Rc};
+
 use anyhow::anyhow;
 
-use crate::{Arity, Expr, Inst, Result, Symbol, compiler::context::LoopContext, expr::vars, op};
+use crate::{
+    Arity, Clause, Expr, FnRef, Inst, Pattern, Result, Symbol, Value,
+    compiler::context::LoopContext, expr::vars, op,
+};
+
+use super::{Compiler, context::Context, pattern};
+
+// A variable needs a box when it is both assigned and captured within its scope.
+fn needs_box(var: Symbol, rest: &[&Expr], body: &Expr) -> bool {
+    let (mut assigned, mut captured) = (false, false);
+    for expr in rest.iter().copied().chain(std::iter::once(body)) {
+        assigned |= expr.assigned_vars().contains(&var);
+        captured |= expr.captured_vars().contains(&var);
+    }
+    assigned && captured
+}
 
-use super::{Compiler, context::Context};
+fn declare_and_set(mut context: Context, var: Symbol, boxed: bool) -> Result<Context> {
+    let index = context.locals.declare_boxed(var, boxed)?;
+    if boxed {
+        context.code.emit(Inst::Box);
+    }
+    context.code.emit(Inst::Set(0, index));
+    Ok(context)
+}
 
 impl Compiler<'_> {
     pub fn compile(&mut self, mut context: Context, expr: &Expr) -> Result<Context> {
@@ -12,32 +36,59 @@ impl Compiler<'_> {
                 Ok(context)
             }
             &Expr::Var(sym) => {
-                let (frame_index, index) = self.lookup(&context, sym)?;
+                let (frame_index, index, boxed) = self.lookup(&context, sym)?;
                 context.code.emit(Inst::Get(frame_index, index));
+                if boxed {
+                    context.code.emit(Inst::Unbox);
+                }
                 Ok(context)
             }
             Expr::List(exprs) => self.compile_list(context, exprs),
+            Expr::Vector(exprs) => self.compile_vector(context, exprs),
+            Expr::Map(entries) => self.compile_map(context, entries),
+            Expr::Set(exprs) => self.compile_set(context, exprs),
+            Expr::Compound { type_, args } => self.compile_compound(context, *type_, args),
             Expr::Do(exprs) => self.compile_do(context, exprs),
             Expr::UnOp { op, expr } => self.compile_unop(context, *op, expr),
             Expr::BinOp { op, left, right } => self.compile_binop(context, *op, left, right),
             Expr::Call { fn_, args } => self.compile_call(context, fn_, args),
             Expr::Fn { params, body } => self.compile_fn(context, params, body),
+            Expr::Assign { var, expr } => self.compile_assign(context, *var, expr),
             Expr::Let {
+                pattern_expr_pairs,
+                body,
+            } => self.compile_let(context, pattern_expr_pairs, body),
+            Expr::LetRec {
                 var_expr_pairs,
                 body,
-            } => self.compile_let(context, var_expr_pairs, body),
+            } => self.compile_letrec(context, var_expr_pairs, body),
             Expr::If {
                 cond_expr_pairs,
                 else_,
             } => self.compile_if(context, cond_expr_pairs, else_),
+            Expr::Match { expr, clauses } => self.compile_match(context, expr, clauses),
             Expr::Loop {
                 var_expr_pairs,
                 body,
             } => self.compile_loop(context, var_expr_pairs, body),
             Expr::Recur { values } => self.compile_recur(context, values),
+            &Expr::Line { line, ref expr } => self.compile_line(context, line, expr),
         }
     }
 
+    // Records where each source line's code starts. Forms nested on their parent's line don't
+    // start a new entry, so a line breakpoint pauses once per visit to the line.
+    fn compile_line(&mut self, mut context: Context, line: u32, expr: &Expr) -> Result<Context> {
+        let enclosing = context.line.replace(line);
+        if enclosing != Some(line) {
+            context.lines.push((context.code.pc(), line));
+        }
+
+        context = self.compile(context, expr)?;
+        context.line = enclosing;
+        Ok(context)
+    }
+
     fn compile_list(&mut self, mut context: Context, exprs: &[Expr]) -> Result<Context> {
         for expr in exprs {
             context = self.compile(context, expr)?;
@@ -49,18 +100,70 @@ impl Compiler<'_> {
         Ok(context)
     }
 
-    fn compile_do(&mut self, mut context: Context, exprs: &[Expr]) -> Result<Context> {
+    fn compile_vector(&mut self, mut context: Context, exprs: &[Expr]) -> Result<Context> {
         for expr in exprs {
             context = self.compile(context, expr)?;
-            context.code.emit(Inst::Drop);
         }
 
         let value_count = u16::try_from(exprs.len()).unwrap();
-        context.code.emit(Inst::List(value_count));
+        context.code.emit(Inst::Vector(value_count));
+
+        Ok(context)
+    }
+
+    fn compile_map(&mut self, mut context: Context, entries: &[(Expr, Expr)]) -> Result<Context> {
+        for (key, value) in entries {
+            context = self.compile(context, key)?;
+            context = self.compile(context, value)?;
+        }
+
+        let entry_count = u16::try_from(entries.len()).unwrap();
+        context.code.emit(Inst::HashMap(entry_count));
 
         Ok(context)
     }
 
+    fn compile_set(&mut self, mut context: Context, exprs: &[Expr]) -> Result<Context> {
+        for expr in exprs {
+            context = self.compile(context, expr)?;
+        }
+
+        let value_count = u16::try_from(exprs.len()).unwrap();
+        context.code.emit(Inst::HashSet(value_count));
+
+        Ok(context)
+    }
+
+    fn compile_compound(
+        &mut self,
+        mut context: Context,
+        type_: Symbol,
+        args: &[Expr],
+    ) -> Result<Context> {
+        for arg in args {
+            context = self.compile(context, arg)?;
+        }
+
+        let value_count = u16::try_from(args.len()).unwrap();
+        context.code.emit(Inst::Compound(type_, value_count));
+
+        Ok(context)
+    }
+
+    fn compile_do(&mut self, mut context: Context, exprs: &[Expr]) -> Result<Context> {
+        let Some((last, exprs)) = exprs.split_last() else {
+            context.code.emit(Inst::Value(Value::nil()));
+            return Ok(context);
+        };
+
+        for expr in exprs {
+            context = self.compile(context, expr)?;
+            context.code.emit(Inst::Drop);
+        }
+
+        self.compile(context, last)
+    }
+
     fn compile_unop(
         &mut self,
         mut context: Context,
@@ -97,23 +200,44 @@ impl Compiler<'_> {
         Ok(context)
     }
 
-    fn compile_fn(
+    fn compile_fn(&mut self, context: Context, params: &[Symbol], body: &Expr) -> Result<Context> {
+        let (context, _) = self.compile_closure(context, params, body)?;
+        Ok(context)
+    }
+
+    // Returns the captured variables in the order of the closure's value slots.
+    fn compile_closure(
         &mut self,
         mut context: Context,
         params: &[Symbol],
         body: &Expr,
-    ) -> Result<Context> {
+    ) -> Result<(Context, Vec<Symbol>)> {
         let fn_vars = vars::list(params);
-        let closure_vars = body.free_vars().difference(fn_vars);
+        let closure_vars = body.free_vars().relative_complement(fn_vars);
+        let mut closure_boxed = Vec::new();
         for &var in &closure_vars {
-            context = self.compile(context, &Expr::var(var))?;
+            // Boxed variables are captured as the box itself so that updates are shared.
+            let (frame_index, index, boxed) = self.lookup(&context, var)?;
+            context.code.emit(Inst::Get(frame_index, index));
+            closure_boxed.push(boxed);
         }
 
         let mut new_context = Context::fn_(&context);
         self.contexts.push(context);
 
-        new_context.locals.declare_all(&closure_vars)?;
-        new_context.locals.declare_all(params)?;
+        for (&var, boxed) in closure_vars.iter().zip(closure_boxed) {
+            new_context.locals.declare_boxed(var, boxed)?;
+        }
+        let boxed_vars = body.assigned_vars().intersection(body.captured_vars());
+        for &param in params {
+            let boxed = boxed_vars.contains(&param);
+            let index = new_context.locals.declare_boxed(param, boxed)?;
+            if boxed {
+                new_context.code.emit(Inst::Get(0, index));
+                new_context.code.emit(Inst::Box);
+                new_context.code.emit(Inst::Set(0, index));
+            }
+        }
 
         let arity = params.len();
 
@@ -126,19 +250,118 @@ impl Compiler<'_> {
             .code
             .emit(Inst::Closure(fn_id, closure_value_count));
 
-        Ok(old_context)
+        Ok((old_context, closure_vars.into_iter().collect()))
+    }
+
+    fn compile_assign(
+        &mut self,
+        mut context: Context,
+        var: Symbol,
+        expr: &Expr,
+    ) -> Result<Context> {
+        let (frame_index, index, boxed) = self.lookup(&context, var)?;
+        if boxed {
+            context.code.emit(Inst::Get(frame_index, index));
+            context = self.compile(context, expr)?;
+            context.code.emit(Inst::SetBox);
+        } else {
+            context = self.compile(context, expr)?;
+            context.code.emit(Inst::Set(frame_index, index));
+            context.code.emit(Inst::Get(frame_index, index));
+        }
+        Ok(context)
     }
 
     fn compile_let(
         &mut self,
         mut context: Context,
-        var_expr_pairs: &[(Symbol, Expr)],
+        pattern_expr_pairs: &[(Pattern, Expr)],
         body: &Expr,
     ) -> Result<Context> {
-        for (var, expr) in var_expr_pairs {
+        for (i, (pattern, expr)) in pattern_expr_pairs.iter().enumerate() {
             context = self.compile(context, expr)?;
-            let index = context.locals.declare(*var)?;
+            let rest: Vec<&Expr> = pattern_expr_pairs[i + 1..]
+                .iter()
+                .map(|(_, expr)| expr)
+                .collect();
+
+            if let &Pattern::Var(var) = pattern {
+                if let (Expr::Fn { .. }, Some(&Inst::Closure(fn_id, _))) =
+                    (expr.unlocated(), context.code.last())
+                {
+                    self.vm.name_function(FnRef::Compiled(fn_id), var);
+                }
+
+                context = declare_and_set(context, var, needs_box(var, &rest, body))?;
+                continue;
+            }
+
+            let value_index = pattern::hide(&mut context, "let")?;
+            let mut slots = HashMap::new();
+            for var in pattern.vars() {
+                let boxed = needs_box(var, &rest, body);
+                slots.insert(var, (context.locals.declare_boxed(var, boxed)?, boxed));
+            }
+            let mut fails = Vec::new();
+            pattern::compile(&mut context, pattern, value_index, &slots, &mut fails)?;
+            if !fails.is_empty() {
+                let exit_point = context.code.bookmark();
+                let fail = context.code.pc();
+                context.code.emit(Inst::Get(0, value_index));
+                context
+                    .code
+                    .emit(Inst::CantDestructure(Rc::new(pattern.clone())));
+                context
+                    .code
+                    .patch(exit_point, Inst::Jump(context.code.pc()));
+                for branch_point in fails {
+                    context.code.patch(branch_point, Inst::JumpIfNot(fail));
+                }
+            }
+        }
+
+        self.compile(context, body)
+    }
+
+    // All closures are created with their siblings' slots still nil, then each closure's captured
+    // siblings are back-patched once every closure exists.
+    fn compile_letrec(
+        &mut self,
+        mut context: Context,
+        var_expr_pairs: &[(Symbol, Expr)],
+        body: &Expr,
+    ) -> Result<Context> {
+        let mut indices = HashMap::new();
+        for &(var, _) in var_expr_pairs {
+            let index = context.locals.declare(var)?;
+            context.code.emit(Inst::Value(Value::nil()));
             context.code.emit(Inst::Set(0, index));
+            indices.insert(var, index);
+        }
+
+        let mut captures = Vec::new();
+        for (var, expr) in var_expr_pairs {
+            let Expr::Fn { params, body } = expr.unlocated() else {
+                return Err(anyhow!("`letrec` binding `{var}` must be a function"));
+            };
+
+            let closure_vars;
+            (context, closure_vars) = self.compile_closure(context, params, body)?;
+            if let Some(&Inst::Closure(fn_id, _)) = context.code.last() {
+                self.vm.name_function(FnRef::Compiled(fn_id), *var);
+            }
+            context.code.emit(Inst::Set(0, indices[var]));
+            captures.push((indices[var], closure_vars));
+        }
+
+        for (index, closure_vars) in captures {
+            for (slot, var) in closure_vars.iter().enumerate() {
+                if let Some(&captured_index) = indices.get(var) {
+                    context.code.emit(Inst::Get(0, index));
+                    context.code.emit(Inst::Get(0, captured_index));
+                    context.code.emit(Inst::Patch(u16::try_from(slot)?));
+                }
+            }
         }
 
         self.compile(context, body)
@@ -187,6 +410,77 @@ impl Compiler<'_> {
         Ok(context)
     }
 
+    // The clauses compile to a decision tree over the scrutinee, followed by their bodies.
+    fn compile_match(
+        &mut self,
+        mut context: Context,
+        expr: &Expr,
+        clauses: &[Clause],
+    ) -> Result<Context> {
+        for (i, clause) in clauses.iter().enumerate() {
+            let covering = clauses[..i].iter().find(|earlier| {
+                earlier.guard.is_none() && earlier.pattern.subsumes(&clause.pattern)
+            });
+            if let Some(earlier) = covering {
+                self.vm.warn(format!(
+                    "unreachable `match` clause {} is covered by {}",
+                    clause.pattern, earlier.pattern
+                ));
+            }
+        }
+
+        context = self.compile(context, expr)?;
+        let scrutinee = pattern::hide(&mut context, "match")?;
+
+        // A variable bound by several clauses shares one slot, boxed if any clause needs it boxed.
+        let mut slots = HashMap::new();
+        for clause in clauses {
+            for var in clause.pattern.vars() {
+                if slots.contains_key(&var) {
+                    continue;
+                }
+                let boxed = clauses.iter().any(|clause| {
+                    let guard: Vec<&Expr> = clause.guard.iter().collect();
+                    clause.pattern.vars().contains(&var) && needs_box(var, &guard, &clause.body)
+                });
+                slots.insert(var, (context.locals.declare_boxed(var, boxed)?, boxed));
+            }
+        }
+
+        let rows = clauses
+            .iter()
+            .enumerate()
+            .map(|(i, clause)| pattern::Row::new(scrutinee, clause.pattern.clone(), i))
+            .collect();
+        let mut tree = pattern::Tree {
+            clauses,
+            slots,
+            scrutinee,
+            leaves: vec![Vec::new(); clauses.len()],
+        };
+        context = self.decide(context, rows, &mut tree)?;
+
+        let mut exit_points = Vec::new();
+        for (clause, leaves) in clauses.iter().zip(tree.leaves) {
+            if leaves.is_empty() {
+                continue;
+            }
+            let body = context.code.pc();
+            for leaf in leaves {
+                context.code.patch(leaf, Inst::Jump(body));
+            }
+            context = self.compile(context, &clause.body)?;
+            exit_points.push(context.code.bookmark());
+        }
+
+        let end = context.code.pc();
+        for exit_point in exit_points {
+            context.code.patch(exit_point, Inst::Jump(end));
+        }
+
+        Ok(context)
+    }
+
     fn compile_loop(
         &mut self,
         mut context: Context,
@@ -199,10 +493,13 @@ impl Compiler<'_> {
 
         let locals_offset = u16::try_from(context.locals.var_count())?;
 
-        for (var, expr) in var_expr_pairs {
+        for (i, (var, expr)) in var_expr_pairs.iter().enumerate() {
             context = self.compile(context, expr)?;
-            let index = context.locals.declare(*var)?;
-            context.code.emit(Inst::Set(0, index));
+            let rest: Vec<&Expr> = var_expr_pairs[i + 1..]
+                .iter()
+                .map(|(_, expr)| expr)
+                .collect();
+            context = declare_and_set(context, *var, needs_box(*var, &rest, body))?;
         }
 
         let loop_body_pc = context.code.pc();
@@ -231,6 +528,10 @@ impl Compiler<'_> {
         for (index_usize, expr) in exprs.iter().enumerate() {
             let index = u16::try_from(index_usize)?;
             context = self.compile(context, expr)?;
+            // Each iteration gets a fresh box, so closures from earlier iterations keep theirs.
+            if self.is_loop_var_boxed(&context, loop_context, index) {
+                context.code.emit(Inst::Box);
+            }
             set_indices.push(index);
         }
 
@@ -16,6 +16,9 @@ pub struct Context {
     pub code: Code,
     #[allow(clippy::struct_field_names)]
     pub loop_context: Option<LoopContext>,
+    // The pc at which each source line's code starts, and the line being compiled.
+    pub lines: Vec<(u32, u32)>,
+    pub line: Option<u32>,
 }
 
 impl Context {
@@ -24,6 +27,8 @@ impl Context {
             locals: Locals::new(),
             code: Code::new(),
             loop_context: None,
+            lines: Vec::new(),
+            line: None,
         }
     }
 
@@ -35,6 +40,8 @@ impl Context {
                 frame_offset: loop_context.frame_offset + 1,
                 ..*loop_context
             }),
+            lines: Vec::new(),
+            line: None,
         }
     }
 
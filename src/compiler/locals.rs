@@ -1,12 +1,13 @@
 use anyhow::anyhow;
-use im::HashMap;
+use im::{HashMap, HashSet};
 
-use crate::{Result, ResultIterator, Symbol};
+use crate::{Result, Symbol};
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct Locals {
     vars: Vec<Symbol>,
     indices: HashMap<Symbol, u16>,
+    boxed: HashSet<Symbol>,
 }
 
 impl Locals {
@@ -14,6 +15,7 @@ impl Locals {
         Locals {
             vars: Vec::new(),
             indices: HashMap::new(),
+            boxed: HashSet::new(),
         }
     }
 
@@ -21,6 +23,10 @@ impl Locals {
         self.vars.len()
     }
 
+    pub fn vars(&self) -> &[Symbol] {
+        &self.vars
+    }
+
     pub fn declare(&mut self, var: Symbol) -> Result<u16> {
         if self.indices.contains_key(&var) {
             return Err(anyhow!("`{var}` is already defined"));
@@ -32,11 +38,16 @@ impl Locals {
         Ok(index)
     }
 
-    pub fn declare_all<'a, I: IntoIterator<Item = &'a Symbol>>(
-        &mut self,
-        vars: I,
-    ) -> Result<Vec<u16>> {
-        vars.into_iter().map(|&var| self.declare(var)).try_collect()
+    pub fn declare_boxed(&mut self, var: Symbol, boxed: bool) -> Result<u16> {
+        let index = self.declare(var)?;
+        if boxed {
+            self.boxed.insert(var);
+        }
+        Ok(index)
+    }
+
+    pub fn is_boxed(&self, var: Symbol) -> bool {
+        self.boxed.contains(&var)
     }
 
     pub fn get_index(&self, var: Symbol) -> Option<u16> {
@@ -2,12 +2,13 @@ mod code;
 mod compile;
 pub mod context;
 mod locals;
+mod pattern;
 
 use anyhow::anyhow;
 
 use crate::{Arity, Expr, FnId, Inst, Result, Symbol, VM};
 
-use self::context::Context;
+use self::context::{Context, LoopContext};
 
 #[derive(Debug)]
 pub struct Compiler<'a> {
@@ -23,21 +24,32 @@ impl<'a> Compiler<'a> {
         }
     }
 
-    fn lookup(&self, context: &Context, sym: Symbol) -> Result<(u16, u16)> {
+    fn lookup(&self, context: &Context, sym: Symbol) -> Result<(u16, u16, bool)> {
         if let Some(index) = context.locals.get_index(sym) {
-            return Ok((0, index));
+            return Ok((0, index, context.locals.is_boxed(sym)));
         }
 
         for (i, context) in self.contexts.iter().rev().enumerate() {
             if let Some(index) = context.locals.get_index(sym) {
                 let frame_index = u16::try_from(i + 1).unwrap();
-                return Ok((frame_index, index));
+                return Ok((frame_index, index, context.locals.is_boxed(sym)));
             }
         }
 
         Err(anyhow!("`{sym}` is not defined"))
     }
 
+    fn is_loop_var_boxed(&self, context: &Context, loop_context: LoopContext, index: u16) -> bool {
+        let frame_offset = usize::from(loop_context.frame_offset);
+        let locals = if frame_offset == 0 {
+            &context.locals
+        } else {
+            &self.contexts[self.contexts.len() - frame_offset].locals
+        };
+        let var = locals.vars()[usize::from(loop_context.locals_offset + index)];
+        locals.is_boxed(var)
+    }
+
     fn create_closure<A: Into<Arity>>(
         &mut self,
         mut context: Context,
@@ -48,7 +60,9 @@ impl<'a> Compiler<'a> {
         context.code.emit(Inst::Return);
 
         let code = context.code.extract();
-        let fn_id = self.vm.register_closure(arity, code);
+        let local_names = context.locals.vars().to_vec();
+        let lines = std::mem::take(&mut context.lines);
+        let fn_id = self.vm.register_closure(arity, code, local_names, lines);
 
         Ok((context, fn_id))
     }
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+
+use crate::{Clause, Inst, Pattern, Result, Shape, Symbol, Value};
+
+use super::{Compiler, context::Context};
+
+// What matching a pattern against a value does at the pattern's root.
+pub enum Node {
+    Bind(Symbol),
+    Any,
+    Or(Vec<Pattern>),
+    // A shape test, then the sub-patterns to match against the parts of the value that each
+    // accessor instruction takes out.
+    Test(Shape, Vec<(Inst, Pattern)>),
+}
+
+impl Node {
+    pub fn new(pattern: &Pattern) -> Result<Node> {
+        let node = match pattern {
+            &Pattern::Var(var) => Node::Bind(var),
+            Pattern::Wildcard => Node::Any,
+            Pattern::Literal(value) => Node::Test(Shape::Equal(value.clone()), Vec::new()),
+            Pattern::List { items, rest } => match (items.split_first(), rest) {
+                (None, None) => Node::Test(Shape::Equal(Value::nil()), Vec::new()),
+                (None, Some(rest)) => Node::new(rest)?,
+                (Some((head, items)), rest) => {
+                    let tail = Pattern::List {
+                        items: items.to_vec(),
+                        rest: rest.clone(),
+                    };
+                    Node::Test(
+                        Shape::Cons,
+                        vec![(Inst::Item(0), head.clone()), (Inst::Item(1), tail)],
+                    )
+                }
+            },
+            Pattern::Vector { items, rest } => {
+                let len = u16::try_from(items.len())?;
+                let mut parts = item_parts(items)?;
+                if let Some(rest) = rest {
+                    parts.push((Inst::Rest(len), (**rest).clone()));
+                }
+                let shape = Shape::Vector {
+                    len,
+                    rest: rest.is_some(),
+                };
+                Node::Test(shape, parts)
+            }
+            &Pattern::Compound { type_, ref items } => Node::Test(
+                Shape::Compound(type_, u16::try_from(items.len())?),
+                item_parts(items)?,
+            ),
+            Pattern::Map(entries) => {
+                let keys = entries.iter().map(|(key, _)| key.clone()).collect();
+                let parts = entries
+                    .iter()
+                    .map(|(key, pattern)| (Inst::Key(key.clone()), pattern.clone()))
+                    .collect();
+                Node::Test(Shape::Map(keys), parts)
+            }
+            Pattern::Or(alternatives) => Node::Or(alternatives.clone()),
+        };
+        Ok(node)
+    }
+}
+
+fn item_parts(items: &[Pattern]) -> Result<Vec<(Inst, Pattern)>> {
+    let mut parts = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        parts.push((Inst::Item(u16::try_from(i)?), item.clone()));
+    }
+    Ok(parts)
+}
+
+// Stores a value in a fresh hidden local, whose name can't be read.
+pub fn hide(context: &mut Context, prefix: &str) -> Result<u16> {
+    let var = Symbol::new(format!("{prefix}.{}", context.locals.var_count()));
+    let index = context.locals.declare(var)?;
+    context.code.emit(Inst::Set(0, index));
+    Ok(index)
+}
+
+// Sets the local of `var` to the value on the stack, boxing it if its slot holds a box.
+pub fn bind(context: &mut Context, var: Symbol, slots: &HashMap<Symbol, (u16, bool)>) {
+    let (index, boxed) = slots[&var];
+    if boxed {
+        context.code.emit(Inst::Box);
+    }
+    context.code.emit(Inst::Set(0, index));
+}
+
+// Emits code that matches the value in local `slot` against `pattern`, setting the slots of the
+// pattern's variables. Each test leaves a bookmark in `fails`, for the caller to patch with a
+// `JumpIfNot` to its failure handling.
+pub fn compile(
+    context: &mut Context,
+    pattern: &Pattern,
+    slot: u16,
+    slots: &HashMap<Symbol, (u16, bool)>,
+    fails: &mut Vec<u32>,
+) -> Result<()> {
+    match Node::new(pattern)? {
+        Node::Bind(var) => {
+            context.code.emit(Inst::Get(0, slot));
+            bind(context, var, slots);
+        }
+        Node::Any => {}
+        Node::Or(alternatives) => {
+            // An alternative that fails partway may have set some variables, but the one that
+            // matches sets them all again.
+            let (last, alternatives) = alternatives.split_last().unwrap();
+            let mut exit_points = Vec::new();
+            for alternative in alternatives {
+                let mut alternative_fails = Vec::new();
+                compile(context, alternative, slot, slots, &mut alternative_fails)?;
+                exit_points.push(context.code.bookmark());
+                let next = context.code.pc();
+                for fail in alternative_fails {
+                    context.code.patch(fail, Inst::JumpIfNot(next));
+                }
+            }
+            compile(context, last, slot, slots, fails)?;
+
+            let end = context.code.pc();
+            for exit_point in exit_points {
+                context.code.patch(exit_point, Inst::Jump(end));
+            }
+        }
+        Node::Test(shape, parts) => {
+            context.code.emit(Inst::Get(0, slot));
+            context.code.emit(Inst::Is(shape));
+            fails.push(context.code.bookmark());
+
+            for (access, part) in parts {
+                match part {
+                    Pattern::Wildcard => {}
+                    Pattern::Var(var) => {
+                        context.code.emit(Inst::Get(0, slot));
+                        context.code.emit(access);
+                        bind(context, var, slots);
+                    }
+                    _ => {
+                        context.code.emit(Inst::Get(0, slot));
+                        context.code.emit(access);
+                        let part_slot = hide(context, "pattern")?;
+                        compile(context, &part, part_slot, slots, fails)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// A `match` clause that may still apply: the patterns left to test, each against the value in a
+// local, and the variables bound so far.
+#[derive(Clone)]
+pub struct Row {
+    tests: Vec<(u16, Pattern)>,
+    binds: Vec<(Symbol, u16)>,
+    clause: usize,
+}
+
+impl Row {
+    pub fn new(slot: u16, pattern: Pattern, clause: usize) -> Row {
+        Row {
+            tests: vec![(slot, pattern)],
+            binds: Vec::new(),
+            clause,
+        }
+    }
+
+    // Moves leading variables and wildcards out of the tests, returning the first real test.
+    fn next_test(&mut self) -> Result<Option<(u16, Node)>> {
+        while let Some(&(slot, ref pattern)) = self.tests.first() {
+            match Node::new(pattern)? {
+                Node::Bind(var) => self.binds.push((var, slot)),
+                Node::Any => {}
+                node => return Ok(Some((slot, node))),
+            }
+            self.tests.remove(0);
+        }
+        Ok(None)
+    }
+}
+
+pub struct Tree<'a> {
+    pub clauses: &'a [Clause],
+    pub slots: HashMap<Symbol, (u16, bool)>,
+    pub scrutinee: u16,
+    // The jumps to each clause's body, to patch once the bodies are compiled.
+    pub leaves: Vec<Vec<u32>>,
+}
+
+impl Compiler<'_> {
+    // Compiles `rows` to a decision tree, so a test shared by several clauses runs once rather
+    // than once per clause. Every path ends by jumping to a clause body or raising an error, and a
+    // failed guard goes on to the rows after its clause.
+    pub(super) fn decide(
+        &mut self,
+        mut context: Context,
+        mut rows: Vec<Row>,
+        tree: &mut Tree<'_>,
+    ) -> Result<Context> {
+        loop {
+            let Some(row) = rows.first_mut() else {
+                context.code.emit(Inst::Get(0, tree.scrutinee));
+                context.code.emit(Inst::NoMatch);
+                return Ok(context);
+            };
+
+            match row.next_test()? {
+                None => {
+                    let row = rows.remove(0);
+                    for (var, slot) in row.binds {
+                        context.code.emit(Inst::Get(0, slot));
+                        bind(&mut context, var, &tree.slots);
+                    }
+
+                    let clauses = tree.clauses;
+                    let Some(guard) = &clauses[row.clause].guard else {
+                        tree.leaves[row.clause].push(context.code.bookmark());
+                        return Ok(context);
+                    };
+                    context = self.compile(context, guard)?;
+                    let branch_point = context.code.bookmark();
+                    tree.leaves[row.clause].push(context.code.bookmark());
+                    let next = context.code.pc();
+                    context.code.patch(branch_point, Inst::JumpIfNot(next));
+                }
+                Some((_, Node::Or(alternatives))) => {
+                    let row = rows.remove(0);
+                    for alternative in alternatives.into_iter().rev() {
+                        let mut row = row.clone();
+                        row.tests[0].1 = alternative;
+                        rows.insert(0, row);
+                    }
+                }
+                Some((slot, Node::Test(shape, parts))) => {
+                    return self.branch(context, rows, slot, &shape, &parts, tree);
+                }
+                Some((_, Node::Bind(_) | Node::Any)) => unreachable!(),
+            }
+        }
+    }
+
+    // Tests the value in `slot`, taking its parts apart if it passes. Rows testing the same shape
+    // continue with their sub-patterns, rows testing a shape it rules out only continue if it
+    // fails, and any other row continues either way.
+    fn branch(
+        &mut self,
+        mut context: Context,
+        rows: Vec<Row>,
+        slot: u16,
+        shape: &Shape,
+        parts: &[(Inst, Pattern)],
+        tree: &mut Tree<'_>,
+    ) -> Result<Context> {
+        context.code.emit(Inst::Get(0, slot));
+        context.code.emit(Inst::Is(shape.clone()));
+        let branch_point = context.code.bookmark();
+
+        let mut part_slots = Vec::new();
+        for (access, _) in parts {
+            context.code.emit(Inst::Get(0, slot));
+            context.code.emit(access.clone());
+            part_slots.push(hide(&mut context, "match")?);
+        }
+
+        let (mut passed, mut failed) = (Vec::new(), Vec::new());
+        for mut row in rows {
+            let Some(i) = row.tests.iter().position(|&(s, _)| s == slot) else {
+                passed.push(row.clone());
+                failed.push(row);
+                continue;
+            };
+            match Node::new(&row.tests[i].1)? {
+                Node::Test(other, other_parts) if other == *shape => {
+                    let tests = part_slots
+                        .iter()
+                        .copied()
+                        .zip(other_parts.into_iter().map(|(_, part)| part));
+                    row.tests.splice(i..=i, tests);
+                    passed.push(row);
+                }
+                Node::Test(other, _) if shape.excludes(&other) => failed.push(row),
+                _ => {
+                    passed.push(row.clone());
+                    failed.push(row);
+                }
+            }
+        }
+
+        context = self.decide(context, passed, tree)?;
+        let next = context.code.pc();
+        context.code.patch(branch_point, Inst::JumpIfNot(next));
+        self.decide(context, failed, tree)
+    }
+}
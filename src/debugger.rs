@@ -0,0 +1,216 @@
+use std::collections::HashSet;
+
+use anyhow::anyhow;
+
+use crate::{Execution, FnId, Frame, Result, Status, Value};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Breakpoint {
+    Function(FnId),
+    Inst(FnId, u32),
+    // A line of a source read with `Module::read_source`.
+    Line(u32),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Pause {
+    Breakpoint(Breakpoint),
+    Step,
+    Finished(Value),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Debugger {
+    breakpoints: HashSet<Breakpoint>,
+}
+
+impl Debugger {
+    #[must_use]
+    pub fn new() -> Self {
+        Debugger::default()
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> bool {
+        self.breakpoints.insert(breakpoint)
+    }
+
+    pub fn remove_breakpoint(&mut self, breakpoint: Breakpoint) -> bool {
+        self.breakpoints.remove(&breakpoint)
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = Breakpoint> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    pub fn break_on(&mut self, func: &Value) -> Result<Breakpoint> {
+        let Value::Closure(closure) = func else {
+            return Err(anyhow!("can't break on {func}"));
+        };
+
+        let breakpoint = Breakpoint::Function(closure.fn_id);
+        self.add_breakpoint(breakpoint);
+        Ok(breakpoint)
+    }
+
+    #[must_use]
+    pub fn hit_breakpoint(&self, execution: &Execution<'_>) -> Option<Breakpoint> {
+        let Some(Frame::Compiled(frame)) = execution.current_frame() else {
+            return None;
+        };
+
+        let inst_breakpoint = Breakpoint::Inst(frame.fn_id, frame.pc);
+        let fn_breakpoint = Breakpoint::Function(frame.fn_id);
+        if self.breakpoints.contains(&inst_breakpoint) {
+            Some(inst_breakpoint)
+        } else if frame.pc == 0 && self.breakpoints.contains(&fn_breakpoint) {
+            Some(fn_breakpoint)
+        } else {
+            execution
+                .vm()
+                .lines_at(frame.fn_id, frame.pc)
+                .map(Breakpoint::Line)
+                .find(|breakpoint| self.breakpoints.contains(breakpoint))
+        }
+    }
+
+    pub fn continue_(&self, execution: &mut Execution<'_>) -> Result<Pause> {
+        self.run_until(execution, |_| false)
+    }
+
+    pub fn step_in(&self, execution: &mut Execution<'_>) -> Result<Pause> {
+        self.run_until(execution, |_| true)
+    }
+
+    pub fn step_over(&self, execution: &mut Execution<'_>) -> Result<Pause> {
+        let depth = execution.frames().len();
+        self.run_until(execution, |execution| execution.frames().len() <= depth)
+    }
+
+    pub fn step_out(&self, execution: &mut Execution<'_>) -> Result<Pause> {
+        let depth = execution.frames().len();
+        self.run_until(execution, |execution| execution.frames().len() < depth)
+    }
+
+    fn run_until<F: Fn(&Execution<'_>) -> bool>(
+        &self,
+        execution: &mut Execution<'_>,
+        stop: F,
+    ) -> Result<Pause> {
+        loop {
+            if let Status::Finished(value) = execution.step(1)? {
+                return Ok(Pause::Finished(value));
+            }
+
+            if !matches!(execution.current_frame(), Some(Frame::Compiled(_))) {
+                continue;
+            }
+
+            if let Some(breakpoint) = self.hit_breakpoint(execution) {
+                return Ok(Pause::Breakpoint(breakpoint));
+            }
+
+            if stop(execution) {
+                return Ok(Pause::Step);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Debugger, Expr, Frame, Module, Symbol, VM, Value, parser};
+
+    use super::{Breakpoint, Pause};
+
+    fn parse_value(s: &str) -> Value {
+        parser::parse(s, parser::value()).unwrap()
+    }
+
+    #[test]
+    fn breakpoints_and_stepping() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+
+        let f = module.eval_str("(fn [x] ($add x 1))").unwrap();
+        module.set("f", f.clone());
+        let env = module.env.clone();
+
+        let mut debugger = Debugger::new();
+        let breakpoint = debugger.break_on(&f).unwrap();
+
+        let value = parse_value("($mul (f 41) 2)");
+        let mut execution = module.start(&value).unwrap();
+
+        let pause = debugger.continue_(&mut execution).unwrap();
+        assert_eq!(Pause::Breakpoint(breakpoint), pause);
+        assert_eq!(2, execution.frames().len());
+        assert_eq!(
+            vec![(Symbol::new("x"), Value::from(41))],
+            execution.frame_locals(1)
+        );
+
+        let expr = Expr::try_from(&parse_value("($sub x 1)")).unwrap();
+        let value = execution.eval_in_frame(1, &env, &expr).unwrap();
+        assert_eq!(Value::from(40), value);
+
+        assert_eq!(Pause::Step, debugger.step_out(&mut execution).unwrap());
+        assert_eq!(1, execution.frames().len());
+        assert_eq!(&[Value::from(42)], execution.values());
+
+        let pause = debugger.continue_(&mut execution).unwrap();
+        assert_eq!(Pause::Finished(Value::from(84)), pause);
+    }
+
+    #[test]
+    fn step_over() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+
+        let f = module.eval_str("(fn [x] ($add x 1))").unwrap();
+        module.set("f", f);
+
+        let debugger = Debugger::new();
+        let value = parse_value("(f 1)");
+        let mut execution = module.start(&value).unwrap();
+
+        let mut steps = 0;
+        while let Pause::Step = debugger.step_over(&mut execution).unwrap() {
+            assert_eq!(1, execution.frames().len());
+            steps += 1;
+        }
+
+        assert_eq!(3, steps);
+        assert!(execution.is_finished());
+    }
+
+    #[test]
+    fn line_breakpoints() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+
+        let source = "(let f (fn [x]\n         ($add x 1))\n  ($mul (f 41)\n        2))";
+        let [value] = Module::read_source(source).unwrap().try_into().unwrap();
+
+        let mut debugger = Debugger::new();
+        debugger.add_breakpoint(Breakpoint::Line(2));
+        let mut execution = module.start(&value).unwrap();
+
+        let pause = debugger.continue_(&mut execution).unwrap();
+        assert_eq!(Pause::Breakpoint(Breakpoint::Line(2)), pause);
+        assert_eq!(
+            vec![(Symbol::new("x"), Value::from(41))],
+            execution.frame_locals(1)
+        );
+        let Some(Frame::Compiled(frame)) = execution.current_frame() else {
+            unreachable!();
+        };
+        assert_eq!(Some(2), execution.vm().line_at(frame.fn_id, frame.pc));
+
+        let pause = debugger.continue_(&mut execution).unwrap();
+        assert_eq!(Pause::Finished(Value::from(84)), pause);
+    }
+}
@@ -0,0 +1,532 @@
+mod native;
+
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+
+use crate::{Pattern, Result, ResultIterator, Rule, Symbol, Value, symbol};
+
+pub(crate) use native::define_all;
+
+pub const DEFAULT_MAX_ITERATIONS: usize = 30;
+pub const DEFAULT_MAX_NODES: usize = 10_000;
+
+pub type ClassId = usize;
+
+// A compound node points at the classes of its arguments; any other value is a leaf.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum ENode {
+    Leaf(Value),
+    Compound(Symbol, Vec<ClassId>),
+}
+
+// Equivalence classes of terms, with a union-find over class ids and a hashcons from
+// canonical nodes to their class. Unions are only propagated by `rebuild`, which restores
+// congruence: nodes whose children are equivalent end up in the same class.
+#[derive(Clone, Debug, Default)]
+pub struct EGraph {
+    parents: Vec<ClassId>,
+    classes: BTreeMap<ClassId, Vec<ENode>>,
+    memo: im::HashMap<ENode, ClassId>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stop {
+    Saturated,
+    IterationLimit,
+    NodeLimit,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Report {
+    pub stop: Stop,
+    pub iterations: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Saturation {
+    rules: Vec<Rule>,
+    max_iterations: usize,
+    max_nodes: usize,
+}
+
+type Bindings = Vec<(Symbol, ClassId)>;
+
+impl ENode {
+    fn canonical(&self, egraph: &EGraph) -> ENode {
+        match self {
+            ENode::Leaf(_) => self.clone(),
+            ENode::Compound(type_, children) => {
+                ENode::Compound(*type_, children.iter().map(|&id| egraph.find(id)).collect())
+            }
+        }
+    }
+}
+
+impl EGraph {
+    #[must_use]
+    pub fn new() -> Self {
+        EGraph::default()
+    }
+
+    #[must_use]
+    pub fn find(&self, mut id: ClassId) -> ClassId {
+        while self.parents[id] != id {
+            id = self.parents[id];
+        }
+        id
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = (ClassId, &[ENode])> {
+        self.classes
+            .iter()
+            .map(|(&id, nodes)| (id, nodes.as_slice()))
+    }
+
+    #[must_use]
+    pub fn class_count(&self) -> usize {
+        self.classes.len()
+    }
+
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        self.classes.values().map(Vec::len).sum()
+    }
+
+    pub fn add_node(&mut self, node: &ENode) -> ClassId {
+        let node = node.canonical(self);
+        if let Some(&id) = self.memo.get(&node) {
+            return self.find(id);
+        }
+
+        let id = self.parents.len();
+        self.parents.push(id);
+        self.classes.insert(id, vec![node.clone()]);
+        self.memo.insert(node, id);
+        id
+    }
+
+    pub fn add(&mut self, value: &Value) -> ClassId {
+        let node = match value {
+            Value::Compound(compound) => {
+                let children = compound.values.iter().map(|v| self.add(v)).collect();
+                ENode::Compound(compound.type_, children)
+            }
+            _ => ENode::Leaf(value.clone()),
+        };
+        self.add_node(&node)
+    }
+
+    // The class of `value` if the e-graph already represents it.
+    #[must_use]
+    pub fn lookup(&self, value: &Value) -> Option<ClassId> {
+        let node = match value {
+            Value::Compound(compound) => {
+                let children = compound
+                    .values
+                    .iter()
+                    .map(|v| self.lookup(v))
+                    .collect::<Option<_>>()?;
+                ENode::Compound(compound.type_, children)
+            }
+            _ => ENode::Leaf(value.clone()),
+        };
+        self.memo
+            .get(&node.canonical(self))
+            .map(|&id| self.find(id))
+    }
+
+    // Merges two classes, returning whether they were distinct. Call `rebuild` afterwards to
+    // restore congruence.
+    pub fn union(&mut self, a: ClassId, b: ClassId) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+
+        let (root, child) = (a.min(b), a.max(b));
+        self.parents[child] = root;
+        if let Some(nodes) = self.classes.remove(&child) {
+            self.classes.entry(root).or_default().extend(nodes);
+        }
+        true
+    }
+
+    pub fn rebuild(&mut self) {
+        loop {
+            let mut memo = im::HashMap::new();
+            let mut unions = Vec::new();
+            let classes = std::mem::take(&mut self.classes);
+            for (id, nodes) in classes {
+                let mut nodes: Vec<ENode> = nodes.iter().map(|node| node.canonical(self)).collect();
+                nodes.sort();
+                nodes.dedup();
+                for node in &nodes {
+                    if let Some(other) = memo.insert(node.clone(), id) {
+                        unions.push((other, id));
+                    }
+                }
+                self.classes.insert(id, nodes);
+            }
+            self.memo = memo;
+
+            let mut changed = false;
+            for (a, b) in unions {
+                changed |= self.union(a, b);
+            }
+            if !changed {
+                return;
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn equivalent(&self, a: ClassId, b: ClassId) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    fn ematch(&self, pattern: &Pattern, id: ClassId, bindings: Bindings) -> Vec<Bindings> {
+        let id = self.find(id);
+        match pattern {
+            Pattern::Wildcard => vec![bindings],
+            &Pattern::Var(var) => match bindings.iter().find(|(bound, _)| *bound == var) {
+                Some(&(_, bound)) if self.find(bound) == id => vec![bindings],
+                Some(_) => vec![],
+                None => {
+                    let mut bindings = bindings;
+                    bindings.push((var, id));
+                    vec![bindings]
+                }
+            },
+            Pattern::Literal(value) => match self.lookup(value) {
+                Some(found) if found == id => vec![bindings],
+                _ => vec![],
+            },
+            Pattern::Compound { type_, items } => self
+                .classes
+                .get(&id)
+                .into_iter()
+                .flatten()
+                .filter_map(|node| match node {
+                    ENode::Compound(t, children) if t == type_ && children.len() == items.len() => {
+                        Some(children)
+                    }
+                    _ => None,
+                })
+                .flat_map(|children| {
+                    items.iter().zip(children).fold(
+                        vec![bindings.clone()],
+                        |partials, (item, &child)| {
+                            partials
+                                .into_iter()
+                                .flat_map(|partial| self.ematch(item, child, partial))
+                                .collect()
+                        },
+                    )
+                })
+                .collect(),
+            Pattern::List { items, rest } => {
+                self.ematch(&cons_pattern(items, rest.as_deref()), id, bindings)
+            }
+            Pattern::Or(alternatives) => alternatives
+                .iter()
+                .flat_map(|alternative| self.ematch(alternative, id, bindings.clone()))
+                .collect(),
+            Pattern::Vector { .. } | Pattern::Map(_) => vec![],
+        }
+    }
+
+    fn instantiate(&mut self, template: &Value, bindings: &Bindings) -> ClassId {
+        match template {
+            &Value::Symbol(sym) => match bindings.iter().find(|(var, _)| *var == sym) {
+                Some(&(_, id)) => id,
+                None => self.add(template),
+            },
+            Value::Compound(quote) if quote.is_quote() => self.add(&quote.values[0]),
+            Value::Compound(compound) => {
+                let children = compound
+                    .values
+                    .iter()
+                    .map(|value| self.instantiate(value, bindings))
+                    .collect();
+                self.add_node(&ENode::Compound(compound.type_, children))
+            }
+            _ => self.add(template),
+        }
+    }
+
+    // The cheapest term in each class, where a term costs the sum of `cost` over its nodes.
+    fn best_costs<F: Fn(&ENode) -> f64>(&self, cost: F) -> BTreeMap<ClassId, (f64, &ENode)> {
+        let mut best: BTreeMap<ClassId, (f64, &ENode)> = BTreeMap::new();
+        loop {
+            let mut changed = false;
+            for (&id, nodes) in &self.classes {
+                for node in nodes {
+                    let children_cost = match node {
+                        ENode::Leaf(_) => Some(0.0),
+                        ENode::Compound(_, children) => children
+                            .iter()
+                            .map(|child| best.get(&self.find(*child)).map(|&(cost, _)| cost))
+                            .sum(),
+                    };
+                    let Some(children_cost) = children_cost else {
+                        continue;
+                    };
+                    let total = cost(node) + children_cost;
+                    if best.get(&id).is_none_or(|&(current, _)| total < current) {
+                        best.insert(id, (total, node));
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                return best;
+            }
+        }
+    }
+
+    // Extracts the cheapest term equivalent to class `id`, returning it with its cost. Costs
+    // must be non-negative, or cycles would keep getting cheaper.
+    pub fn extract_with<F: Fn(&ENode) -> f64>(&self, id: ClassId, cost: F) -> Result<(Value, f64)> {
+        fn build(
+            egraph: &EGraph,
+            best: &BTreeMap<ClassId, (f64, &ENode)>,
+            id: ClassId,
+        ) -> Result<Value> {
+            let Some(&(_, node)) = best.get(&egraph.find(id)) else {
+                return Err(anyhow!("e-class {id} has no finite term"));
+            };
+            match node {
+                ENode::Leaf(value) => Ok(value.clone()),
+                ENode::Compound(type_, children) => Ok(Value::compound(
+                    *type_,
+                    children
+                        .iter()
+                        .map(|&child| build(egraph, best, child))
+                        .try_collect()?,
+                )),
+            }
+        }
+
+        if id >= self.parents.len() {
+            return Err(anyhow!("no e-class {id}"));
+        }
+        let best = self.best_costs(cost);
+        match best.get(&self.find(id)) {
+            Some(&(total, _)) => Ok((build(self, &best, id)?, total)),
+            None => Err(anyhow!("e-class {id} has no finite term")),
+        }
+    }
+
+    // Extracts the smallest term equivalent to class `id`.
+    pub fn extract(&self, id: ClassId) -> Result<Value> {
+        Ok(self.extract_with(id, |_| 1.0)?.0)
+    }
+}
+
+fn cons_pattern(items: &[Pattern], rest: Option<&Pattern>) -> Pattern {
+    match items.split_first() {
+        Some((item, items)) => Pattern::Compound {
+            type_: *symbol::CONS,
+            items: vec![item.clone(), cons_pattern(items, rest)],
+        },
+        None => rest
+            .cloned()
+            .unwrap_or_else(|| Pattern::Literal(Value::nil())),
+    }
+}
+
+fn check_pattern(pattern: &Pattern) -> Result<()> {
+    match pattern {
+        Pattern::Var(_) | Pattern::Wildcard | Pattern::Literal(_) => Ok(()),
+        Pattern::List { items, rest } => {
+            items.iter().try_for_each(check_pattern)?;
+            rest.as_deref().map_or(Ok(()), check_pattern)
+        }
+        Pattern::Compound { items, .. } | Pattern::Or(items) => {
+            items.iter().try_for_each(check_pattern)
+        }
+        Pattern::Vector { .. } | Pattern::Map(_) => {
+            Err(anyhow!("e-graph rules can't match {pattern}"))
+        }
+    }
+}
+
+impl Saturation {
+    #[must_use]
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Saturation {
+            rules,
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            max_nodes: DEFAULT_MAX_NODES,
+        }
+    }
+
+    #[must_use]
+    pub fn max_iterations(self, max_iterations: usize) -> Self {
+        Saturation {
+            max_iterations,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn max_nodes(self, max_nodes: usize) -> Self {
+        Saturation { max_nodes, ..self }
+    }
+
+    // Applies every rule at every class until nothing changes or a limit is reached. Matches
+    // are collected before any are applied, so the result doesn't depend on rule order.
+    pub fn run(&self, egraph: &mut EGraph) -> Result<Report> {
+        for rule in &self.rules {
+            check_pattern(&rule.pattern)?;
+        }
+
+        for iteration in 0..self.max_iterations {
+            let mut matches = Vec::new();
+            for rule in &self.rules {
+                for &id in egraph.classes.keys() {
+                    for bindings in egraph.ematch(&rule.pattern, id, Vec::new()) {
+                        matches.push((id, &rule.replacement, bindings));
+                    }
+                }
+            }
+
+            let mut changed = false;
+            for (id, replacement, bindings) in matches {
+                let new_id = egraph.instantiate(replacement, &bindings);
+                changed |= egraph.union(id, new_id);
+                if egraph.node_count() > self.max_nodes {
+                    egraph.rebuild();
+                    return Ok(Report {
+                        stop: Stop::NodeLimit,
+                        iterations: iteration + 1,
+                    });
+                }
+            }
+            egraph.rebuild();
+
+            if !changed {
+                return Ok(Report {
+                    stop: Stop::Saturated,
+                    iterations: iteration + 1,
+                });
+            }
+        }
+
+        Ok(Report {
+            stop: Stop::IterationLimit,
+            iterations: self.max_iterations,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Module, Rule, VM, Value, parser};
+
+    use super::{EGraph, ENode, Saturation, Stop};
+
+    fn parse(s: &str) -> Value {
+        parser::parse(s, parser::value()).unwrap()
+    }
+
+    fn rule(pattern: &str, replacement: &str) -> Rule {
+        Rule::new(&parse(pattern), parse(replacement)).unwrap()
+    }
+
+    #[test]
+    fn congruence() {
+        let mut egraph = EGraph::new();
+        let fa = egraph.add(&parse("(#f a)"));
+        let fb = egraph.add(&parse("(#f b)"));
+        assert!(!egraph.equivalent(fa, fb));
+
+        let a = egraph.add(&parse("a"));
+        let b = egraph.add(&parse("b"));
+        egraph.union(a, b);
+        egraph.rebuild();
+        assert!(egraph.equivalent(fa, fb));
+        assert_eq!(Some(egraph.find(fa)), egraph.lookup(&parse("(#f b)")));
+    }
+
+    #[test]
+    fn saturation() {
+        let mut egraph = EGraph::new();
+        let id = egraph.add(&parse("(#div (#mul 2 a) 2)"));
+        let rules = vec![
+            rule("(#mul x 2)", "(#shl x 1)"),
+            rule("(#div (#mul x y) y)", "x"),
+            rule("(#mul x y)", "(#mul y x)"),
+        ];
+        let report = Saturation::new(rules).run(&mut egraph).unwrap();
+        assert_eq!(Stop::Saturated, report.stop);
+        assert_eq!(parse("a"), egraph.extract(id).unwrap());
+
+        let product = egraph.lookup(&parse("(#mul 2 a)")).unwrap();
+        let (term, cost) = egraph
+            .extract_with(product, |node| match node {
+                ENode::Compound(type_, _) if type_.as_str() == "mul" => 4.0,
+                _ => 1.0,
+            })
+            .unwrap();
+        assert_eq!((parse("(#shl a 1)"), 3.0), (term, cost));
+
+        let rules = vec![
+            rule("(#div (#mul x y) z)", "(#mul x (#div y z))"),
+            rule("(#mul x 1)", "x"),
+            rule("(#mul x y)", "(#mul y x)"),
+        ];
+        let mut egraph = EGraph::new();
+        egraph.add(&parse("(#div (#mul a 2) 2)"));
+        let report = Saturation::new(rules)
+            .max_nodes(8)
+            .run(&mut egraph)
+            .unwrap();
+        assert_eq!(Stop::NodeLimit, report.stop);
+    }
+
+    #[test]
+    fn natives() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+
+        assert_eq!(
+            "a",
+            module
+                .eval_str(
+                    "(let [g id] (add-term (egraph) '#div(#mul(a 2) 2))
+                          [saturated _] (saturate g (rules '[(#div (#mul x y) y) -> x]))
+                          [term _] (extract saturated id)
+                       term)"
+                )
+                .unwrap()
+                .to_string()
+        );
+
+        // Adding to a graph other than the last one returned decodes it again.
+        assert_eq!(
+            "[(#egraph [0 1 2] {0 [a] 1 [(#f 0)] 2 [(#g 0)]}) (#egraph [0 1 2] {0 [a] 1 [(#f 0)] 2 [b]})]",
+            module
+                .eval_str(
+                    "(let [g _] (add-term (egraph) '#f(a))
+                          [h _] (add-term g '#g(a))
+                          [i _] (add-term g 'b)
+                       [h i])"
+                )
+                .unwrap()
+                .to_string()
+        );
+
+        let err = module.eval_str("(saturate (egraph) 1)").unwrap_err();
+        assert_eq!("expected list or vector, got 1", err.to_string());
+
+        let err = module
+            .eval_str("(saturate '#egraph([0 1] {0 [#f(1)]}) (rules '[(#f (#g x)) -> x]))")
+            .unwrap_err();
+        assert_eq!(
+            "malformed e-graph (#egraph [0 1] {0 [(#f 1)]})",
+            err.to_string()
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use std::cell::RefCell;
+
+use anyhow::anyhow;
+use dumpster::unsync::Gc;
+
+use crate::{
+    Module, Pretty, Result, ResultIterator, Symbol, Value, error::expected, rewrite, try_as_array,
+    value::Compound,
+};
+
+use super::{ClassId, DEFAULT_MAX_ITERATIONS, DEFAULT_MAX_NODES, EGraph, ENode, Saturation, Stop};
+
+// E-graphs are values in jymbol, `(#egraph parents classes)`, where `parents` is the
+// union-find vector and `classes` maps each root id to its nodes. A compound node is written
+// as a compound of class ids, so `(#add 0 1)`; leaves are written as themselves.
+fn egraph_type() -> Symbol {
+    Symbol::new("egraph")
+}
+
+// The e-graph value returned last, with the native graph it encodes. A graph built up term by
+// term passes each result to the next call, which then takes the native graph from here rather
+// than decoding the whole value again.
+thread_local! {
+    static LAST: RefCell<Option<(Gc<Compound>, EGraph)>> = const { RefCell::new(None) };
+}
+
+// The native graph of `value` if it is the e-graph returned last.
+fn take_last(value: &Value) -> Option<EGraph> {
+    let Value::Compound(compound) = value else {
+        return None;
+    };
+    LAST.with(|last| {
+        let mut last = last.borrow_mut();
+        match last.take() {
+            Some((last, egraph)) if Gc::ptr_eq(&last, compound) => Some(egraph),
+            other => {
+                *last = other;
+                None
+            }
+        }
+    })
+}
+
+fn set_last(value: &Value, egraph: EGraph) -> Result<()> {
+    let entry = Some((value.as_compound()?, egraph));
+    // Swap the old entry out first so dropping it can't reenter the borrow.
+    let old = LAST.with(|last| last.replace(entry));
+    drop(old);
+    Ok(())
+}
+
+fn id_value(id: ClassId) -> Value {
+    Value::from(i64::try_from(id).unwrap_or(i64::MAX))
+}
+
+fn to_id(value: &Value) -> Result<ClassId> {
+    Ok(usize::try_from(value.as_integer()?)?)
+}
+
+fn node_value(node: &ENode) -> Value {
+    match node {
+        ENode::Leaf(value) => value.clone(),
+        ENode::Compound(type_, children) => {
+            Value::compound(*type_, children.iter().map(|&id| id_value(id)).collect())
+        }
+    }
+}
+
+fn to_node(value: &Value) -> Result<ENode> {
+    match value {
+        Value::Compound(compound) => Ok(ENode::Compound(
+            compound.type_,
+            compound.values.iter().map(to_id).try_collect()?,
+        )),
+        _ => Ok(ENode::Leaf(value.clone())),
+    }
+}
+
+fn class_value(nodes: &[ENode]) -> Value {
+    nodes
+        .iter()
+        .map(node_value)
+        .collect::<im::Vector<_>>()
+        .into()
+}
+
+fn egraph_value(egraph: &EGraph) -> Value {
+    let parents: Vec<Value> = egraph.parents.iter().map(|&id| id_value(id)).collect();
+    let classes: im::HashMap<Value, Value> = egraph
+        .classes()
+        .map(|(id, nodes)| (id_value(id), class_value(nodes)))
+        .collect();
+    Value::compound(egraph_type(), vec![Value::vector(parents), classes.into()])
+}
+
+// `value` with the classes from `len` on added, for an e-graph that has only gained classes
+// since `value` encoded it. The rest of the value is shared rather than encoded again.
+fn grown_value(value: &Value, egraph: &EGraph, len: usize) -> Result<Value> {
+    let compound = value.as_compound()?;
+    let [parents, classes] = compound.as_checked_array(egraph_type())?;
+    let mut parents = (*parents.as_vector()?).clone();
+    let mut classes = (*classes.as_map()?).clone();
+    parents.extend(egraph.parents[len..].iter().map(|&id| id_value(id)));
+    for (&id, nodes) in egraph.classes.range(len..) {
+        classes.insert(id_value(id), class_value(nodes));
+    }
+    Ok(Value::compound(
+        egraph_type(),
+        vec![parents.into(), classes.into()],
+    ))
+}
+
+fn to_egraph(value: &Value) -> Result<EGraph> {
+    let compound = value.as_compound()?;
+    let [parents, classes] = compound.as_checked_array(egraph_type())?;
+
+    let mut egraph = EGraph::new();
+    egraph.parents = parents.as_vector()?.iter().map(to_id).try_collect()?;
+    for (id, nodes) in classes.as_map()?.iter() {
+        let nodes = nodes.as_vector()?.iter().map(to_node).try_collect()?;
+        egraph.classes.insert(to_id(id)?, nodes);
+    }
+
+    // Parents never point at later classes, which also rules out cycles, and only roots have
+    // classes.
+    let len = egraph.parents.len();
+    let children = egraph
+        .classes
+        .values()
+        .flatten()
+        .flat_map(|node| match node {
+            ENode::Compound(_, children) => children.as_slice(),
+            ENode::Leaf(_) => &[],
+        });
+    let malformed = egraph
+        .parents
+        .iter()
+        .enumerate()
+        .any(|(id, &parent)| parent > id)
+        || children.into_iter().any(|&id| id >= len)
+        || !(0..len)
+            .filter(|&id| egraph.parents[id] == id)
+            .eq(egraph.classes.keys().copied());
+    if malformed {
+        return Err(anyhow!(
+            "malformed e-graph {}",
+            Pretty::brief().render(value)
+        ));
+    }
+
+    egraph.rebuild();
+    Ok(egraph)
+}
+
+// Decodes `value`, unless it is the e-graph returned last.
+fn native_egraph(value: &Value) -> Result<EGraph> {
+    match take_last(value) {
+        Some(egraph) => Ok(egraph),
+        None => to_egraph(value),
+    }
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn egraph(_values: &[Value]) -> Result<Value> {
+    Ok(egraph_value(&EGraph::new()))
+}
+
+// `(add-term g term)` returns `[g id]`.
+fn add_term(values: &[Value]) -> Result<Value> {
+    let [value, term] = try_as_array(values)?;
+    // Only a graph decoded here may have been rebuilt into something the value doesn't show.
+    let last = take_last(value);
+    let grown = last.is_some();
+    let mut egraph = match last {
+        Some(egraph) => egraph,
+        None => to_egraph(value)?,
+    };
+    let len = egraph.parents.len();
+    let id = egraph.add(term);
+    let value = if grown {
+        grown_value(value, &egraph, len)?
+    } else {
+        egraph_value(&egraph)
+    };
+    set_last(&value, egraph)?;
+    Ok(Value::vector([value, id_value(id)]))
+}
+
+// `(saturate g rules [max-iterations [max-nodes]])` returns `[g stop]`, where `stop` is
+// `:saturated`, `:iteration-limit` or `:node-limit`.
+fn saturate(values: &[Value]) -> Result<Value> {
+    let (egraph, rules, limits) = match values {
+        [egraph, rules, limits @ ..] if limits.len() <= 2 => (egraph, rules, limits),
+        _ => return Err(anyhow!("expected 2 to 4 arguments, got {}", values.len())),
+    };
+
+    let limit = |i: usize, default: usize| match limits.get(i) {
+        Some(limit) => Ok(usize::try_from(limit.as_integer()?)?),
+        None => Ok::<_, anyhow::Error>(default),
+    };
+    let rules = rewrite::to_rules(rules)?;
+    let saturation = Saturation::new(rules)
+        .max_iterations(limit(0, DEFAULT_MAX_ITERATIONS)?)
+        .max_nodes(limit(1, DEFAULT_MAX_NODES)?);
+
+    let mut egraph = native_egraph(egraph)?;
+    let stop = match saturation.run(&mut egraph)?.stop {
+        Stop::Saturated => "saturated",
+        Stop::IterationLimit => "iteration-limit",
+        Stop::NodeLimit => "node-limit",
+    };
+    let value = egraph_value(&egraph);
+    set_last(&value, egraph)?;
+    Ok(Value::vector([value, Value::keyword(stop)]))
+}
+
+// `(extract g id [costs])` returns `[term cost]`. `costs` maps compound types to the cost of
+// one node; other nodes, including leaves, cost 1.
+fn extract(values: &[Value]) -> Result<Value> {
+    let (egraph, id, costs) = match values {
+        [egraph, id] => (egraph, id, None),
+        [egraph, id, costs] => (egraph, id, Some(costs.as_map()?)),
+        _ => return Err(anyhow!("expected 2 or 3 arguments, got {}", values.len())),
+    };
+
+    let mut node_costs = Vec::new();
+    for (type_, cost) in costs.iter().flat_map(|costs| costs.iter()) {
+        let cost = cost
+            .to_f64()
+            .filter(|cost| *cost >= 0.0)
+            .ok_or_else(|| expected("non-negative cost", cost))?;
+        node_costs.push((type_.as_symbol()?, cost));
+    }
+    let cost = |node: &ENode| match node {
+        ENode::Compound(type_, _) => node_costs
+            .iter()
+            .find(|(t, _)| t == type_)
+            .map_or(1.0, |&(_, cost)| cost),
+        ENode::Leaf(_) => 1.0,
+    };
+
+    let id = to_id(id)?;
+    let native = native_egraph(egraph)?;
+    let extracted = native.extract_with(id, cost);
+    set_last(egraph, native)?;
+    let (term, total) = extracted?;
+    Ok(Value::vector([term, total.into()]))
+}
+
+pub fn define_all(module: &mut Module) {
+    module.set_native("egraph", egraph, 0);
+    module.set_native("add-term", add_term, 2);
+    module.set_native("saturate", saturate, 2..);
+    module.set_native("extract", extract, 2..);
+}
@@ -1,2 +1,11 @@
+use anyhow::anyhow;
+
+use crate::{Pretty, Value};
+
 pub type Error = anyhow::Error;
 pub type Result<T> = std::result::Result<T, Error>;
+
+// A type error for a value, laid out briefly since it may be a large term.
+pub(crate) fn expected(what: &str, value: &Value) -> Error {
+    anyhow!("expected {what}, got {}", Pretty::brief().render(value))
+}
@@ -1,6 +1,10 @@
 use anyhow::anyhow;
+use im::HashSet;
 
-use crate::{Error, Expr, Result, ResultIterator, Symbol, Value, op, special, try_as_array};
+use crate::{
+    Clause, Error, Expr, Pattern, Result, ResultIterator, Symbol, Value, op, special, symbol,
+    try_as_array,
+};
 
 fn check_var_is_valid(var: Symbol) -> Result<()> {
     if special::VARS.contains(&var) {
@@ -18,7 +22,7 @@ impl Expr {
 
     pub fn value(value: &Value) -> Result<Self> {
         match value {
-            Value::Compound(cons) if cons.is_cons() => {
+            Value::Compound(cons) if cons.is_cons() && value.is_proper_list() => {
                 let values = value.into_iter().map(Expr::value).try_collect()?;
                 Ok(Expr::List(values))
             }
@@ -44,12 +48,40 @@ impl Expr {
         })
     }
 
-    pub fn let_(var_expr_pairs: Vec<(Symbol, Expr)>, body: Expr) -> Result<Self> {
-        for &(var, _) in &var_expr_pairs {
-            check_var_is_valid(var)?;
+    pub fn let_(pattern_expr_pairs: Vec<(Pattern, Expr)>, body: Expr) -> Result<Self> {
+        for (pattern, _) in &pattern_expr_pairs {
+            for var in pattern.vars() {
+                check_var_is_valid(var)?;
+            }
         }
 
         Ok(Expr::Let {
+            pattern_expr_pairs,
+            body: Box::new(body),
+        })
+    }
+
+    pub fn letrec(var_expr_pairs: Vec<(Symbol, Expr)>, body: Expr) -> Result<Self> {
+        for (var, expr) in &var_expr_pairs {
+            check_var_is_valid(*var)?;
+            if !matches!(expr.unlocated(), Expr::Fn { .. }) {
+                return Err(anyhow!("`letrec` binding `{var}` must be a function"));
+            }
+        }
+
+        let assigned_vars = var_expr_pairs
+            .iter()
+            .map(|(_, expr)| expr.assigned_vars())
+            .sum::<HashSet<_>>()
+            .union(body.assigned_vars());
+        if let Some(&(var, _)) = var_expr_pairs
+            .iter()
+            .find(|(var, _)| assigned_vars.contains(var))
+        {
+            return Err(anyhow!("can't assign to `letrec` function `{var}`"));
+        }
+
+        Ok(Expr::LetRec {
             var_expr_pairs,
             body: Box::new(body),
         })
@@ -73,6 +105,16 @@ impl Expr {
         })
     }
 
+    pub fn try_from_set(values: &[Value]) -> Result<Self> {
+        let [var_value, value] = try_as_array(values)?;
+        let var = var_value.clone().try_into()?;
+        check_var_is_valid(var)?;
+        Ok(Expr::Assign {
+            var,
+            expr: Box::new(value.try_into()?),
+        })
+    }
+
     pub fn try_from_recur(raw_values: &[Value]) -> Result<Self> {
         let values = raw_values.iter().map(Expr::try_from).try_collect()?;
         Ok(Expr::Recur { values })
@@ -82,14 +124,55 @@ impl Expr {
         match value {
             &Value::Symbol(sym) => Ok(Expr::var(sym)),
             Value::Compound(cons) if cons.is_cons() => {
+                if !value.is_proper_list() {
+                    return Err(anyhow!("can't evaluate dotted list {value}"));
+                }
                 let (fn_value, values_list) = cons.as_cons()?;
                 let values = values_list.into_iter().cloned().collect::<Vec<_>>();
-                Expr::try_from_application(&fn_value, &values)
+                let expr = Expr::try_from_application(&fn_value, &values)?;
+                Ok(match cons.line() {
+                    Some(line) => Expr::Line {
+                        line,
+                        expr: Box::new(expr),
+                    },
+                    None => expr,
+                })
+            }
+            Value::Vector(values) => {
+                let exprs = values.iter().map(Expr::try_from).try_collect()?;
+                Ok(Expr::Vector(exprs))
+            }
+            Value::Map(entries) => {
+                let exprs = entries
+                    .sorted()
+                    .into_iter()
+                    .map(|(key, value)| Result::Ok((key.try_into()?, value.try_into()?)))
+                    .try_collect()?;
+                Ok(Expr::Map(exprs))
+            }
+            Value::Set(values) => {
+                let exprs = values
+                    .sorted()
+                    .into_iter()
+                    .map(Expr::try_from)
+                    .try_collect()?;
+                Ok(Expr::Set(exprs))
             }
             Value::Compound(quote) if quote.is_quote() => {
                 let [value] = try_as_array(&quote.values)?;
                 Expr::value(value)
             }
+            Value::Compound(compound)
+                if !(compound.is_quasiquote()
+                    || compound.is_unquote()
+                    || compound.is_unquote_splicing()) =>
+            {
+                let args = compound.values.iter().map(Expr::try_from).try_collect()?;
+                Ok(Expr::Compound {
+                    type_: compound.type_,
+                    args,
+                })
+            }
             _ => Expr::value(value),
         }
     }
@@ -140,14 +223,52 @@ impl Expr {
 
     pub fn try_from_fn(values: &[Value]) -> Result<Expr> {
         let [params_list, body_value] = try_as_array(values)?;
-        let params = params_list
-            .into_iter()
-            .map(|value| value.clone().try_into())
-            .try_collect()?;
-        let body = body_value.try_into()?;
+        let param_values = Expr::try_items(params_list, "fn")?;
+        let mut patterns = Vec::new();
+        let params = Expr::hide_patterns("arg", &param_values, &mut patterns)?;
+        let body = Expr::destructure_hidden(patterns, body_value.try_into()?)?;
         Expr::fn_(params, body)
     }
 
+    // The items of a bracketed part of a special form, which may be written as a list or a vector.
+    fn try_items(value: &Value, form: &str) -> Result<Vec<Value>> {
+        if value.is_proper_list() || matches!(value, Value::Vector(_)) {
+            Ok(value.iter().cloned().collect())
+        } else {
+            Err(anyhow!("malformed `{form}` expression"))
+        }
+    }
+
+    // Replaces destructuring patterns with hidden variables, whose names can't be read, returning
+    // the variables and collecting the patterns to bind from them.
+    fn hide_patterns(
+        prefix: &str,
+        values: &[Value],
+        patterns: &mut Vec<(Pattern, Expr)>,
+    ) -> Result<Vec<Symbol>> {
+        let mut vars = Vec::new();
+        for (i, value) in values.iter().enumerate() {
+            if let &Value::Symbol(var) = value
+                && var != *symbol::WILDCARD
+            {
+                vars.push(var);
+            } else {
+                let var = Symbol::new(format!("{prefix}.{i}"));
+                vars.push(var);
+                patterns.push((Pattern::try_from(value)?, Expr::Var(var)));
+            }
+        }
+        Ok(vars)
+    }
+
+    fn destructure_hidden(patterns: Vec<(Pattern, Expr)>, body: Expr) -> Result<Expr> {
+        if patterns.is_empty() {
+            Ok(body)
+        } else {
+            Expr::let_(patterns, body)
+        }
+    }
+
     pub fn try_from_let(values: &[Value]) -> Result<Expr> {
         match values {
             [var_value_pairs @ .., body_value] => {
@@ -155,17 +276,25 @@ impl Expr {
                     return Err(anyhow!("malformed `let` expression"));
                 }
 
-                let var_expr_pairs = var_value_pairs
+                let pattern_expr_pairs = var_value_pairs
                     .chunks_exact(2)
-                    .map(Expr::try_from_var_expr_pair)
+                    .map(Expr::try_from_pattern_expr_pair)
                     .try_collect()?;
                 let body = body_value.try_into()?;
-                Expr::let_(var_expr_pairs, body)
+                Expr::let_(pattern_expr_pairs, body)
             }
             _ => Err(anyhow!("malformed `let` expression")),
         }
     }
 
+    fn try_from_pattern_expr_pair(values: &[Value]) -> Result<(Pattern, Expr)> {
+        if let [pattern_value, value] = values {
+            Result::Ok((pattern_value.try_into()?, value.try_into()?))
+        } else {
+            Err(anyhow!("malformed `let` expression"))
+        }
+    }
+
     fn try_from_var_expr_pair(values: &[Value]) -> Result<(Symbol, Expr)> {
         if let [var_value, value] = values {
             Result::Ok((var_value.clone().try_into()?, value.try_into()?))
@@ -174,6 +303,38 @@ impl Expr {
         }
     }
 
+    pub fn try_from_letrec(values: &[Value]) -> Result<Expr> {
+        let [var_value_pairs_list, body_value] = try_as_array(values)?;
+        let var_value_pairs = Expr::try_items(var_value_pairs_list, "letrec")?;
+        if !var_value_pairs.len().is_multiple_of(2) {
+            return Err(anyhow!("malformed `letrec` expression"));
+        }
+
+        let var_expr_pairs = var_value_pairs
+            .chunks_exact(2)
+            .map(Expr::try_from_var_expr_pair)
+            .try_collect()?;
+        let body = body_value.try_into()?;
+        Expr::letrec(var_expr_pairs, body)
+    }
+
+    // `(letfn [(f [x] ...) (g [y] ...)] body)` is shorthand for a `letrec` of functions.
+    pub fn try_from_letfn(values: &[Value]) -> Result<Expr> {
+        let [fn_values_list, body_value] = try_as_array(values)?;
+        let var_expr_pairs = Expr::try_items(fn_values_list, "letfn")?
+            .iter()
+            .map(|fn_value| {
+                let fn_values = Expr::try_items(fn_value, "letfn")?;
+                let [var_value, fn_values @ ..] = fn_values.as_slice() else {
+                    return Err(anyhow!("malformed `letfn` expression"));
+                };
+                Ok((var_value.clone().try_into()?, Expr::try_from_fn(fn_values)?))
+            })
+            .try_collect()?;
+        let body = body_value.try_into()?;
+        Expr::letrec(var_expr_pairs, body)
+    }
+
     pub fn try_from_if(values: &[Value]) -> Result<Expr> {
         match values {
             [cond_value_pairs @ .., else_value] => {
@@ -200,20 +361,64 @@ impl Expr {
         }
     }
 
+    pub fn try_from_match(values: &[Value]) -> Result<Expr> {
+        let Some((expr_value, mut clause_values)) = values.split_first() else {
+            return Err(anyhow!("malformed `match` expression"));
+        };
+
+        let mut clauses = Vec::new();
+        while !clause_values.is_empty() {
+            let (pattern_value, guard_value, body_value);
+            (pattern_value, guard_value, body_value, clause_values) = match clause_values {
+                [pattern, Value::Keyword(when), guard, body, rest @ ..]
+                    if *when == *symbol::WHEN =>
+                {
+                    (pattern, Some(guard), body, rest)
+                }
+                [pattern, body, rest @ ..] => (pattern, None, body, rest),
+                _ => return Err(anyhow!("malformed `match` expression")),
+            };
+
+            let pattern = Pattern::try_from(pattern_value)?;
+            for var in pattern.vars() {
+                check_var_is_valid(var)?;
+            }
+            if let Some(var) = pattern.repeated_var() {
+                return Err(anyhow!("`{var}` is bound twice in pattern {pattern}"));
+            }
+            clauses.push(Clause {
+                pattern,
+                guard: guard_value.map(Expr::try_from).transpose()?,
+                body: body_value.try_into()?,
+            });
+        }
+
+        Ok(Expr::Match {
+            expr: Box::new(expr_value.try_into()?),
+            clauses,
+        })
+    }
+
     pub fn try_from_loop(values: &[Value]) -> Result<Expr> {
         match values {
             [var_value_pairs_list, body_value] => {
                 let var_value_pairs: Vec<Value> = var_value_pairs_list.iter().cloned().collect();
-                if var_value_pairs.len() % 2 != 0 {
+                if !var_value_pairs.len().is_multiple_of(2) {
                     return Err(anyhow!("malformed `loop` expression"));
                 }
 
-                let var_expr_pairs = var_value_pairs
-                    .chunks_exact(2)
-                    .map(Expr::try_from_var_expr_pair)
+                let pattern_values: Vec<Value> =
+                    var_value_pairs.iter().step_by(2).cloned().collect();
+                let mut patterns = Vec::new();
+                let vars = Expr::hide_patterns("loop", &pattern_values, &mut patterns)?;
+                let exprs: Vec<Expr> = var_value_pairs
+                    .iter()
+                    .skip(1)
+                    .step_by(2)
+                    .map(Expr::try_from)
                     .try_collect()?;
-                let body = body_value.try_into()?;
-                Expr::loop_(var_expr_pairs, body)
+                let body = Expr::destructure_hidden(patterns, body_value.try_into()?)?;
+                Expr::loop_(vars.into_iter().zip(exprs).collect(), body)
             }
             _ => Err(anyhow!("malformed `loop` expression")),
         }
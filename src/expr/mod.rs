@@ -1,13 +1,30 @@
 mod from;
+mod pattern;
 pub mod vars;
 
+pub use pattern::Pattern;
+
 use crate::{Symbol, Value, op};
 
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Clause {
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: Expr,
+}
+
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Expr {
     Value(Value),
     Var(Symbol),
     List(Vec<Expr>),
+    Vector(Vec<Expr>),
+    Map(Vec<(Expr, Expr)>),
+    Set(Vec<Expr>),
+    Compound {
+        type_: Symbol,
+        args: Vec<Expr>,
+    },
     Do(Vec<Expr>),
     UnOp {
         op: op::Unary,
@@ -26,7 +43,15 @@ pub enum Expr {
         params: Vec<Symbol>,
         body: Box<Expr>,
     },
+    Assign {
+        var: Symbol,
+        expr: Box<Expr>,
+    },
     Let {
+        pattern_expr_pairs: Vec<(Pattern, Expr)>,
+        body: Box<Expr>,
+    },
+    LetRec {
         var_expr_pairs: Vec<(Symbol, Expr)>,
         body: Box<Expr>,
     },
@@ -34,6 +59,10 @@ pub enum Expr {
         cond_expr_pairs: Vec<(Expr, Expr)>,
         else_: Box<Expr>,
     },
+    Match {
+        expr: Box<Expr>,
+        clauses: Vec<Clause>,
+    },
     Loop {
         var_expr_pairs: Vec<(Symbol, Expr)>,
         body: Box<Expr>,
@@ -41,4 +70,20 @@ pub enum Expr {
     Recur {
         values: Vec<Expr>,
     },
+    // A form read from `line` of a source.
+    Line {
+        line: u32,
+        expr: Box<Expr>,
+    },
+}
+
+impl Expr {
+    // The expression without any source line annotations around it.
+    #[must_use]
+    pub fn unlocated(&self) -> &Expr {
+        match self {
+            Expr::Line { expr, .. } => expr.unlocated(),
+            _ => self,
+        }
+    }
 }
@@ -0,0 +1,434 @@
+use std::fmt::{self, Display, Formatter};
+
+use anyhow::anyhow;
+
+use crate::{Error, Pretty, Result, ResultIterator, Symbol, Value, special, symbol};
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Pattern {
+    Var(Symbol),
+    Wildcard,
+    Literal(Value),
+    List {
+        items: Vec<Pattern>,
+        rest: Option<Box<Pattern>>,
+    },
+    Vector {
+        items: Vec<Pattern>,
+        rest: Option<Box<Pattern>>,
+    },
+    Compound {
+        type_: Symbol,
+        items: Vec<Pattern>,
+    },
+    Map(Vec<(Value, Pattern)>),
+    Or(Vec<Pattern>),
+}
+
+impl Pattern {
+    // Variables in the order their values are produced by `matches`.
+    pub fn vars(&self) -> Vec<Symbol> {
+        let mut vars = Vec::new();
+        self.collect_vars(&mut vars);
+        vars
+    }
+
+    // A variable bound more than once. Rewrite rules use these to match equal parts, but `match`
+    // and `let` bind each variable once.
+    pub fn repeated_var(&self) -> Option<Symbol> {
+        let mut vars = self.vars();
+        vars.sort();
+        vars.windows(2)
+            .find_map(|pair| (pair[0] == pair[1]).then_some(pair[0]))
+    }
+
+    fn collect_vars(&self, vars: &mut Vec<Symbol>) {
+        match self {
+            &Pattern::Var(var) => vars.push(var),
+            Pattern::Wildcard | Pattern::Literal(_) => {}
+            Pattern::List { items, rest } | Pattern::Vector { items, rest } => {
+                for item in items {
+                    item.collect_vars(vars);
+                }
+                if let Some(rest) = rest {
+                    rest.collect_vars(vars);
+                }
+            }
+            Pattern::Compound { items, .. } => {
+                for item in items {
+                    item.collect_vars(vars);
+                }
+            }
+            Pattern::Map(entries) => {
+                for (_, pattern) in entries {
+                    pattern.collect_vars(vars);
+                }
+            }
+            Pattern::Or(alternatives) => alternatives[0].collect_vars(vars),
+        }
+    }
+
+    // Pushes the values bound to `vars()` onto `out`, or returns false if the shape doesn't match.
+    // On failure `out` may contain partial bindings.
+    pub fn matches(&self, value: &Value, out: &mut Vec<Value>) -> bool {
+        match self {
+            Pattern::Var(_) => {
+                out.push(value.clone());
+                true
+            }
+            Pattern::Wildcard => true,
+            Pattern::Literal(literal) => literal == value,
+            Pattern::List { items, rest } => {
+                let mut value = value;
+                for item in items {
+                    let Value::Compound(cons) = value else {
+                        return false;
+                    };
+                    let Ok([head, tail]) = cons.as_checked_array(*symbol::CONS) else {
+                        return false;
+                    };
+                    if !item.matches(head, out) {
+                        return false;
+                    }
+                    value = tail;
+                }
+
+                match rest {
+                    Some(rest) => rest.matches(value, out),
+                    None => value.is_nil(),
+                }
+            }
+            Pattern::Vector { items, rest } => {
+                let Value::Vector(values) = value else {
+                    return false;
+                };
+                let len_matches = match rest {
+                    Some(_) => values.len() >= items.len(),
+                    None => values.len() == items.len(),
+                };
+                if !len_matches
+                    || !items
+                        .iter()
+                        .zip(values.iter())
+                        .all(|(p, v)| p.matches(v, out))
+                {
+                    return false;
+                }
+
+                match rest {
+                    Some(rest) => {
+                        let rest_values: Vec<Value> =
+                            values.iter().skip(items.len()).cloned().collect();
+                        rest.matches(&Value::vector(rest_values), out)
+                    }
+                    None => true,
+                }
+            }
+            Pattern::Compound { type_, items } => {
+                let Value::Compound(compound) = value else {
+                    return false;
+                };
+                compound.type_ == *type_
+                    && compound.values.len() == items.len()
+                    && items
+                        .iter()
+                        .zip(&compound.values)
+                        .all(|(item, value)| item.matches(value, out))
+            }
+            Pattern::Map(entries) => {
+                let Value::Map(map) = value else {
+                    return false;
+                };
+                entries.iter().all(|(key, pattern)| {
+                    map.get(key)
+                        .is_some_and(|value| pattern.matches(value, out))
+                })
+            }
+            Pattern::Or(alternatives) => {
+                let start = out.len();
+                for alternative in alternatives {
+                    if alternative.matches(value, out) {
+                        // Alternatives bind the same variables, possibly in a different order.
+                        let bound = out.split_off(start);
+                        let order = alternative.vars();
+                        out.extend(self.vars().iter().filter_map(|var| {
+                            let i = order.iter().position(|v| v == var)?;
+                            Some(bound[i].clone())
+                        }));
+                        return true;
+                    }
+                    out.truncate(start);
+                }
+                false
+            }
+        }
+    }
+
+    // Whether every value matched by `other` is also matched by this pattern. This is
+    // conservative: false means "not known to subsume".
+    pub fn subsumes(&self, other: &Pattern) -> bool {
+        match (self, other) {
+            (Pattern::Var(_) | Pattern::Wildcard, _) => true,
+            (_, Pattern::Or(alternatives)) => alternatives.iter().all(|alt| self.subsumes(alt)),
+            (Pattern::Or(alternatives), _) => alternatives.iter().any(|alt| alt.subsumes(other)),
+            (Pattern::Literal(a), Pattern::Literal(b)) => a == b,
+            (
+                Pattern::List { items, rest },
+                Pattern::List {
+                    items: other_items,
+                    rest: other_rest,
+                },
+            )
+            | (
+                Pattern::Vector { items, rest },
+                Pattern::Vector {
+                    items: other_items,
+                    rest: other_rest,
+                },
+            ) => {
+                let rest_subsumes = match (rest, other_rest) {
+                    (None, None) => items.len() == other_items.len(),
+                    (Some(rest), _) => {
+                        other_items.len() >= items.len()
+                            && matches!(**rest, Pattern::Var(_) | Pattern::Wildcard)
+                    }
+                    (None, Some(_)) => false,
+                };
+                rest_subsumes
+                    && items
+                        .iter()
+                        .zip(other_items)
+                        .all(|(item, other)| item.subsumes(other))
+            }
+            (
+                Pattern::Compound { type_, items },
+                Pattern::Compound {
+                    type_: other_type,
+                    items: other_items,
+                },
+            ) => {
+                type_ == other_type
+                    && items.len() == other_items.len()
+                    && items
+                        .iter()
+                        .zip(other_items)
+                        .all(|(item, other)| item.subsumes(other))
+            }
+            (Pattern::Map(entries), Pattern::Map(other_entries)) => {
+                entries.iter().all(|(key, pattern)| {
+                    other_entries
+                        .iter()
+                        .any(|(other_key, other)| key == other_key && pattern.subsumes(other))
+                })
+            }
+            _ => false,
+        }
+    }
+
+    fn try_from_items(values: &[Value]) -> Result<Vec<Pattern>> {
+        values.iter().map(Pattern::try_from).try_collect()
+    }
+
+    fn try_from_list(value: &Value) -> Result<Pattern> {
+        let mut items = Vec::new();
+        let mut tail = value;
+        while let Value::Compound(cons) = tail
+            && cons.is_cons()
+        {
+            let [head, rest] = cons.as_array()?;
+            items.push(Pattern::try_from(head)?);
+            tail = rest;
+        }
+
+        let rest = if tail.is_nil() {
+            None
+        } else {
+            Some(Box::new(Pattern::try_from(tail)?))
+        };
+        Ok(Pattern::List { items, rest })
+    }
+
+    fn try_from_or(values: &[Value]) -> Result<Pattern> {
+        let alternatives = Pattern::try_from_items(values)?;
+        let Some(first) = alternatives.first() else {
+            return Err(anyhow!("empty `:or` pattern"));
+        };
+
+        let mut vars = first.vars();
+        vars.sort();
+        for alternative in &alternatives[1..] {
+            let mut alternative_vars = alternative.vars();
+            alternative_vars.sort();
+            if alternative_vars != vars {
+                return Err(anyhow!(
+                    "`:or` pattern alternatives must bind the same variables"
+                ));
+            }
+        }
+
+        Ok(Pattern::Or(alternatives))
+    }
+
+    fn try_from_vector(values: &[Value]) -> Result<Pattern> {
+        let (values, rest) = match values {
+            [values @ .., Value::Symbol(amp), rest] if *amp == *symbol::AMPERSAND => {
+                (values, Some(Box::new(Pattern::try_from(rest)?)))
+            }
+            _ => (values, None),
+        };
+        let items = Pattern::try_from_items(values)?;
+        Ok(Pattern::Vector { items, rest })
+    }
+}
+
+impl TryFrom<&Value> for Pattern {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Pattern> {
+        match value {
+            &Value::Symbol(sym) if sym == *symbol::WILDCARD => Ok(Pattern::Wildcard),
+            &Value::Symbol(sym) if special::VARS.contains(&sym) => {
+                if sym == *symbol::NIL || sym == *symbol::TRUE || sym == *symbol::FALSE {
+                    Ok(Pattern::Literal(value.clone()))
+                } else {
+                    Err(anyhow!("can't bind reserved symbol `{sym}`"))
+                }
+            }
+            &Value::Symbol(sym) => Ok(Pattern::Var(sym)),
+            Value::Compound(cons)
+                if cons.is_cons() && cons.values.first() == Some(&Value::Keyword(*symbol::OR)) =>
+            {
+                let [_, alternatives] = cons.as_array()?;
+                if !alternatives.is_proper_list() {
+                    return Err(anyhow!("invalid pattern {}", Pretty::brief().render(value)));
+                }
+                let values: Vec<Value> = alternatives.iter().cloned().collect();
+                Pattern::try_from_or(&values)
+            }
+            Value::Compound(cons) if cons.is_cons() => Pattern::try_from_list(value),
+            Value::Compound(quote) if quote.is_quote() => {
+                let [literal] = quote.as_array()?;
+                Ok(Pattern::Literal(literal.clone()))
+            }
+            Value::Compound(compound)
+                if !(compound.is_quasiquote()
+                    || compound.is_unquote()
+                    || compound.is_unquote_splicing()) =>
+            {
+                Ok(Pattern::Compound {
+                    type_: compound.type_,
+                    items: Pattern::try_from_items(&compound.values)?,
+                })
+            }
+            Value::Vector(values) => {
+                let values: Vec<Value> = values.iter().cloned().collect();
+                Pattern::try_from_vector(&values)
+            }
+            Value::Map(map) => {
+                let entries = map
+                    .sorted()
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let key = match key {
+                            Value::Compound(quote) if quote.is_quote() => {
+                                let [key] = quote.as_array()?;
+                                key.clone()
+                            }
+                            _ => key.clone(),
+                        };
+                        Result::Ok((key, Pattern::try_from(value)?))
+                    })
+                    .try_collect()?;
+                Ok(Pattern::Map(entries))
+            }
+            _ if value.is_number()
+                || matches!(
+                    value,
+                    Value::String(_) | Value::Char(_) | Value::Keyword(_) | Value::Bytes(_)
+                ) =>
+            {
+                Ok(Pattern::Literal(value.clone()))
+            }
+            _ => Err(anyhow!("invalid pattern {}", Pretty::brief().render(value))),
+        }
+    }
+}
+
+fn fmt_literal(f: &mut Formatter<'_>, value: &Value) -> fmt::Result {
+    let self_evaluating = value.is_nil()
+        || value.is_boolean()
+        || !matches!(
+            value,
+            Value::Symbol(_)
+                | Value::Compound(_)
+                | Value::Vector(_)
+                | Value::Map(_)
+                | Value::Set(_)
+        );
+    if self_evaluating {
+        write!(f, "{value}")
+    } else {
+        write!(f, "'{value}")
+    }
+}
+
+fn fmt_items(f: &mut Formatter<'_>, items: &[Pattern]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "{item}")?;
+    }
+    Ok(())
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Var(var) => write!(f, "{var}"),
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Literal(value) => fmt_literal(f, value),
+            Pattern::List { items, rest } => {
+                write!(f, "(")?;
+                fmt_items(f, items)?;
+                if let Some(rest) = rest {
+                    write!(f, " . {rest}")?;
+                }
+                write!(f, ")")
+            }
+            Pattern::Vector { items, rest } => {
+                write!(f, "[")?;
+                fmt_items(f, items)?;
+                if let Some(rest) = rest {
+                    write!(f, " & {rest}")?;
+                }
+                write!(f, "]")
+            }
+            Pattern::Compound { type_, items } => {
+                write!(f, "(#{type_}")?;
+                for item in items {
+                    write!(f, " {item}")?;
+                }
+                write!(f, ")")
+            }
+            Pattern::Or(alternatives) => {
+                write!(f, "(:or")?;
+                for alternative in alternatives {
+                    write!(f, " {alternative}")?;
+                }
+                write!(f, ")")
+            }
+            Pattern::Map(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, pattern)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    fmt_literal(f, key)?;
+                    write!(f, " {pattern}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
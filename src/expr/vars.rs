@@ -13,24 +13,119 @@ pub fn list(vars: &[Symbol]) -> HashSet<Symbol> {
 }
 
 impl Expr {
+    fn children(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Value(_) | Expr::Var(_) => Vec::new(),
+            Expr::List(exprs)
+            | Expr::Vector(exprs)
+            | Expr::Set(exprs)
+            | Expr::Do(exprs)
+            | Expr::Compound { args: exprs, .. }
+            | Expr::Recur { values: exprs } => exprs.iter().collect(),
+            Expr::Map(entries) => entries
+                .iter()
+                .flat_map(|(key, value)| [key, value])
+                .collect(),
+            Expr::Call { fn_, args } => std::iter::once(&**fn_).chain(args).collect(),
+            Expr::UnOp { expr, .. } | Expr::Assign { expr, .. } | Expr::Line { expr, .. } => {
+                vec![expr]
+            }
+            Expr::BinOp { left, right, .. } => vec![left, right],
+            Expr::Fn { body, .. } => vec![body],
+            Expr::Let {
+                pattern_expr_pairs,
+                body,
+            } => pattern_expr_pairs
+                .iter()
+                .map(|(_, expr)| expr)
+                .chain(std::iter::once(&**body))
+                .collect(),
+            Expr::LetRec {
+                var_expr_pairs,
+                body,
+            }
+            | Expr::Loop {
+                var_expr_pairs,
+                body,
+            } => var_expr_pairs
+                .iter()
+                .map(|(_, expr)| expr)
+                .chain(std::iter::once(&**body))
+                .collect(),
+            Expr::Match { expr, clauses } => {
+                std::iter::once(&**expr)
+                    .chain(clauses.iter().flat_map(|clause| {
+                        clause.guard.iter().chain(std::iter::once(&clause.body))
+                    }))
+                    .collect()
+            }
+            Expr::If {
+                cond_expr_pairs,
+                else_,
+            } => cond_expr_pairs
+                .iter()
+                .flat_map(|(cond, expr)| [cond, expr])
+                .chain(std::iter::once(&**else_))
+                .collect(),
+        }
+    }
+
+    // Free variables that are targets of `set!` somewhere in this expression.
+    pub fn assigned_vars(&self) -> HashSet<Symbol> {
+        let vars: HashSet<Symbol> = match self {
+            Expr::Assign { var, expr } => expr.assigned_vars().update(*var),
+            _ => self.children().into_iter().map(Expr::assigned_vars).sum(),
+        };
+        vars.intersection(self.free_vars())
+    }
+
+    // Free variables that are captured by a closure somewhere in this expression.
+    pub fn captured_vars(&self) -> HashSet<Symbol> {
+        let vars: HashSet<Symbol> = match self {
+            Expr::Fn { .. } => self.free_vars(),
+            _ => self.children().into_iter().map(Expr::captured_vars).sum(),
+        };
+        vars.intersection(self.free_vars())
+    }
+
     pub fn free_vars(&self) -> HashSet<Symbol> {
         match self {
             Expr::Value(_) => HashSet::new(),
             &Expr::Var(var) => single(var),
-            Expr::List(exprs) | Expr::Do(exprs) => exprs.iter().map(Expr::free_vars).sum(),
+            Expr::List(exprs) | Expr::Vector(exprs) | Expr::Set(exprs) | Expr::Do(exprs) => {
+                exprs.iter().map(Expr::free_vars).sum()
+            }
+            Expr::Map(entries) => entries
+                .iter()
+                .map(|(key, value)| key.free_vars() + value.free_vars())
+                .sum(),
+            Expr::Compound { args, .. } => args.iter().map(Expr::free_vars).sum(),
             Expr::Call { fn_, args } => fn_.free_vars() + args.iter().map(Expr::free_vars).sum(),
-            Expr::UnOp { expr, .. } => expr.free_vars(),
+            Expr::UnOp { expr, .. } | Expr::Line { expr, .. } => expr.free_vars(),
             Expr::BinOp { left, right, .. } => left.free_vars() + right.free_vars(),
             Expr::Fn { params, body } => {
                 let body_vars = body.free_vars();
                 let bound_vars = list(params);
-                body_vars.difference(bound_vars)
+                body_vars.relative_complement(bound_vars)
             }
+            Expr::Assign { var, expr } => expr.free_vars().update(*var),
             Expr::Let {
-                var_expr_pairs,
+                pattern_expr_pairs,
                 body,
+            } => {
+                let mut vars = HashSet::new();
+                let mut bound_vars = HashSet::new();
+                for (pattern, expr) in pattern_expr_pairs {
+                    bound_vars.extend(pattern.vars());
+                    let expr_vars = expr.free_vars().relative_complement(bound_vars.clone());
+                    vars.extend(expr_vars);
+                }
+
+                let body_vars = body.free_vars().relative_complement(bound_vars);
+                vars.extend(body_vars);
+                vars
             }
-            | Expr::Loop {
+            Expr::Loop {
                 var_expr_pairs,
                 body,
             } => {
@@ -46,6 +141,27 @@ impl Expr {
                 vars.extend(body_vars);
                 vars
             }
+            Expr::LetRec {
+                var_expr_pairs,
+                body,
+            } => {
+                let bound_vars: HashSet<Symbol> =
+                    var_expr_pairs.iter().map(|(var, _)| *var).collect();
+                var_expr_pairs
+                    .iter()
+                    .map(|(_, expr)| expr.free_vars())
+                    .sum::<HashSet<_>>()
+                    .union(body.free_vars())
+                    .relative_complement(bound_vars)
+            }
+            Expr::Match { expr, clauses } => {
+                let clause_vars = clauses.iter().map(|clause| {
+                    let vars = clause.guard.iter().map(Expr::free_vars).sum::<HashSet<_>>()
+                        + clause.body.free_vars();
+                    vars.relative_complement(list(&clause.pattern.vars()))
+                });
+                expr.free_vars() + clause_vars.sum()
+            }
             Expr::If {
                 cond_expr_pairs,
                 else_,
@@ -1,13 +1,41 @@
-use std::fmt::{self, Display, Formatter};
+use std::{
+    cell::{Cell, Ref, RefCell},
+    fmt::{self, Debug, Display, Formatter},
+};
 
 use dumpster::Trace;
 
 use crate::{FnId, Value};
 
-#[derive(Clone, PartialEq, Debug, Trace)]
+#[derive(Clone, Trace)]
 pub struct Closure {
     pub fn_id: FnId,
-    pub values: Vec<Value>,
+    values: RefCell<Vec<Value>>,
+    recursive: Cell<bool>,
+}
+
+impl Closure {
+    pub fn new(fn_id: FnId, values: Vec<Value>) -> Self {
+        Closure {
+            fn_id,
+            values: RefCell::new(values),
+            recursive: Cell::new(false),
+        }
+    }
+
+    pub fn values(&self) -> Ref<'_, Vec<Value>> {
+        self.values.borrow()
+    }
+
+    // Replaces a captured value after creation, which is how `letrec` ties the knot.
+    pub fn patch(&self, index: usize, value: Value) {
+        self.values.borrow_mut()[index] = value;
+        self.recursive.set(true);
+    }
+
+    pub fn is_recursive(&self) -> bool {
+        self.recursive.get()
+    }
 }
 
 impl Display for Closure {
@@ -15,3 +43,14 @@ impl Display for Closure {
         write!(f, "(#fn ...)")
     }
 }
+
+// Patched closures may capture themselves, so don't recurse into the captured values.
+impl Debug for Closure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Closure")
+            .field("fn_id", &self.fn_id)
+            .field("values", &self.values.borrow().len())
+            .field("recursive", &self.recursive.get())
+            .finish()
+    }
+}
@@ -1,18 +1,30 @@
-use crate::{Arity, FnId, Inst};
+use crate::{Arity, FnId, Inst, Symbol};
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct Compiled {
     pub fn_id: FnId,
     pub arity: Arity,
     pub code: Vec<Inst>,
+    pub local_names: Vec<Symbol>,
+    // `(pc, line)` for each point where the code of a source line starts, in pc order.
+    pub lines: Vec<(u32, u32)>,
+    pub name: Option<Symbol>,
 }
 
 impl Compiled {
-    pub fn new<A: Into<Arity>>(fn_id: FnId, arity: A, code: Vec<Inst>) -> Self {
+    pub fn new<A: Into<Arity>>(
+        fn_id: FnId,
+        arity: A,
+        code: Vec<Inst>,
+        local_names: Vec<Symbol>,
+    ) -> Self {
         Compiled {
             fn_id,
             arity: arity.into(),
             code,
+            local_names,
+            lines: Vec::new(),
+            name: None,
         }
     }
 }
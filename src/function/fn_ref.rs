@@ -0,0 +1,29 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::{FnId, Value};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FnRef {
+    Compiled(FnId),
+    Native(FnId),
+}
+
+impl FnRef {
+    #[must_use]
+    pub fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Closure(closure) => Some(FnRef::Compiled(closure.fn_id)),
+            &Value::NativeFunction(fn_id) => Some(FnRef::Native(fn_id)),
+            _ => None,
+        }
+    }
+}
+
+impl Display for FnRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FnRef::Compiled(fn_id) => write!(f, "fn#{fn_id}"),
+            FnRef::Native(fn_id) => write!(f, "native-fn#{fn_id}"),
+        }
+    }
+}
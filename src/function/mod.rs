@@ -1,9 +1,11 @@
 mod closure;
 mod compiled;
 mod fn_id;
+mod fn_ref;
 mod native;
 
 pub use closure::Closure;
 pub use compiled::Compiled;
 pub use fn_id::FnId;
+pub use fn_ref::FnRef;
 pub use native::{Native, RawFn};
@@ -3,7 +3,7 @@ use std::{
     fmt::{self, Debug, Formatter},
 };
 
-use crate::{Arity, FnId, Result, Value};
+use crate::{Arity, FnId, Result, Symbol, Value};
 
 pub type RawFn = fn(&[Value]) -> Result<Value>;
 
@@ -12,6 +12,7 @@ pub struct Native {
     pub id: FnId,
     pub arity: Arity,
     pub function: Box<RawFn>,
+    pub name: Option<Symbol>,
 }
 
 impl Native {
@@ -20,6 +21,7 @@ impl Native {
             id,
             arity: arity.into(),
             function: function.into(),
+            name: None,
         }
     }
 
@@ -46,6 +48,7 @@ impl Debug for Native {
         f.debug_struct("Native")
             .field("id", &self.id)
             .field("arity", &self.arity)
+            .field("name", &self.name)
             .finish_non_exhaustive()
     }
 }
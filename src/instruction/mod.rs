@@ -1,6 +1,11 @@
 pub mod op;
+mod shape;
 
-use crate::{FnId, Symbol, Value};
+use std::rc::Rc;
+
+use crate::{FnId, Pattern, Symbol, Value};
+
+pub use shape::Shape;
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum Inst {
@@ -8,8 +13,21 @@ pub enum Inst {
     Drop,
     Value(Value),
     List(u16),
+    Vector(u16),
+    HashMap(u16),
+    HashSet(u16),
     Compound(Symbol, u16),
     Closure(FnId, u16),
+    Box,
+    Unbox,
+    SetBox,
+    Patch(u16),
+    Is(Shape),
+    Item(u16),
+    Rest(u16),
+    Key(Value),
+    CantDestructure(Rc<Pattern>),
+    NoMatch,
     UnOp(op::Unary),
     BinOp(op::Binary),
     Get(u16, u16),
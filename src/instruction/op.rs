@@ -1,6 +1,13 @@
-use std::{cmp, ops};
+use std::{cmp::Ordering, ops};
 
-use crate::{Result, Value};
+use anyhow::anyhow;
+
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_rational::BigRational;
+use num_traits::{Signed, Zero};
+
+use crate::{Error, Result, Value, error::expected};
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Unary {
@@ -17,16 +24,24 @@ pub enum Unary {
 
 impl Unary {
     pub fn apply(self, value: &Value) -> Result<Value> {
+        match (self, value) {
+            (Unary::Abs, Value::Complex(num)) => Ok(num.norm().into()),
+            (Unary::Neg, Value::Complex(num)) => Ok((-num.0).into()),
+            _ => self.apply_real(value),
+        }
+    }
+
+    fn apply_real(self, value: &Value) -> Result<Value> {
         match self {
-            Unary::Abs => unary_float_op(value, f64::abs),
-            Unary::Neg => unary_float_op(value, ops::Neg::neg),
-            Unary::Sqrt => unary_float_op(value, f64::sqrt),
-            Unary::Trunc => unary_float_op(value, f64::trunc),
-            Unary::Fract => unary_float_op(value, f64::fract),
-            Unary::Round => unary_float_op(value, f64::round),
-            Unary::Floor => unary_float_op(value, f64::floor),
-            Unary::Ceil => unary_float_op(value, f64::ceil),
-            Unary::Not => unary_int_op(value, ops::Not::not),
+            Unary::Abs => unary_num_op(value, i64::checked_abs, Signed::abs, f64::abs),
+            Unary::Neg => unary_num_op(value, i64::checked_neg, |num| -num, ops::Neg::neg),
+            Unary::Sqrt => sqrt(value),
+            Unary::Trunc => unary_num_op(value, Some, BigRational::trunc, f64::trunc),
+            Unary::Fract => unary_num_op(value, |_| Some(0), BigRational::fract, f64::fract),
+            Unary::Round => unary_num_op(value, Some, BigRational::round, f64::round),
+            Unary::Floor => unary_num_op(value, Some, BigRational::floor, f64::floor),
+            Unary::Ceil => unary_num_op(value, Some, BigRational::ceil, f64::ceil),
+            Unary::Not => unary_int_op(value, ops::Not::not, ops::Not::not),
         }
     }
 }
@@ -53,41 +68,127 @@ pub enum Binary {
 }
 
 impl Binary {
-    #[allow(clippy::cast_possible_truncation, clippy::float_cmp)]
     pub fn apply(self, a: &Value, b: &Value) -> Result<Value> {
         match self {
-            Binary::Add => binary_float_op(a, b, ops::Add::add),
-            Binary::Sub => binary_float_op(a, b, ops::Sub::sub),
-            Binary::Mul => binary_float_op(a, b, ops::Mul::mul),
-            Binary::Div => binary_float_op(a, b, ops::Div::div),
-            Binary::Mod => binary_float_op(a, b, ops::Rem::rem),
-            Binary::Pow => binary_float_op(a, b, f64::powf),
-            Binary::Shl => binary_int_op(a, b, ops::Shl::shl),
-            Binary::Shr => binary_int_op(a, b, ops::Shr::shr),
-            Binary::And => binary_int_op(a, b, ops::BitAnd::bitand),
-            Binary::Or => binary_int_op(a, b, ops::BitOr::bitor),
-            Binary::Xor => binary_int_op(a, b, ops::BitXor::bitxor),
-            Binary::Eq => bool_op(a, b, cmp::PartialEq::eq),
-            Binary::Ne => bool_op(a, b, cmp::PartialEq::ne),
-            Binary::Lt => bool_op(a, b, cmp::PartialOrd::lt),
-            Binary::Gt => bool_op(a, b, cmp::PartialOrd::gt),
-            Binary::Le => bool_op(a, b, cmp::PartialOrd::le),
-            Binary::Ge => bool_op(a, b, cmp::PartialOrd::ge),
+            Binary::Add if is_complex_pair(a, b) => binary_complex_op(a, b, ops::Add::add),
+            Binary::Sub if is_complex_pair(a, b) => binary_complex_op(a, b, ops::Sub::sub),
+            Binary::Mul if is_complex_pair(a, b) => binary_complex_op(a, b, ops::Mul::mul),
+            Binary::Div if is_complex_pair(a, b) => binary_complex_op(a, b, ops::Div::div),
+            Binary::Pow if is_complex_pair(a, b) => binary_complex_op(a, b, Complex64::powc),
+            Binary::Add => binary_num_op(
+                a,
+                b,
+                i64::checked_add,
+                |x, y| x + y,
+                |x, y| x + y,
+                ops::Add::add,
+            ),
+            Binary::Sub => binary_num_op(
+                a,
+                b,
+                i64::checked_sub,
+                |x, y| x - y,
+                |x, y| x - y,
+                ops::Sub::sub,
+            ),
+            Binary::Mul => binary_num_op(
+                a,
+                b,
+                i64::checked_mul,
+                |x, y| x * y,
+                |x, y| x * y,
+                ops::Mul::mul,
+            ),
+            Binary::Div => div(a, b),
+            Binary::Mod => rem(a, b),
+            Binary::Pow => pow(a, b),
+            Binary::Shl => shl(a, b),
+            Binary::Shr => shr(a, b),
+            Binary::And => binary_int_op(a, b, |x, y| x & y, |x, y| x & y),
+            Binary::Or => binary_int_op(a, b, |x, y| x | y, |x, y| x | y),
+            Binary::Xor => binary_int_op(a, b, |x, y| x ^ y, |x, y| x ^ y),
+            Binary::Eq => Ok(num_eq(a, b).into()),
+            Binary::Ne => Ok((!num_eq(a, b)).into()),
+            Binary::Lt => cmp_op(a, b, Ordering::is_lt),
+            Binary::Gt => cmp_op(a, b, Ordering::is_gt),
+            Binary::Le => cmp_op(a, b, Ordering::is_le),
+            Binary::Ge => cmp_op(a, b, Ordering::is_ge),
         }
     }
 }
 
+fn expected_integer(value: &Value) -> Error {
+    expected("integer", value)
+}
+
+fn division_by_zero() -> Error {
+    anyhow!("division by zero")
+}
+
 fn unary_float_op<F: Fn(f64) -> f64>(value: &Value, f: F) -> Result<Value> {
     let num = value.as_number()?;
     let num_result = f(num);
     Ok(num_result.into())
 }
 
-#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
-fn unary_int_op<F: Fn(i64) -> i64>(value: &Value, f: F) -> Result<Value> {
+fn unary_num_op<F, G, H>(value: &Value, int_op: F, exact_op: G, float_op: H) -> Result<Value>
+where
+    F: Fn(i64) -> Option<i64>,
+    G: Fn(&BigRational) -> BigRational,
+    H: Fn(f64) -> f64,
+{
+    if let &Value::Integer(num) = value
+        && let Some(num_result) = int_op(num)
+    {
+        return Ok(num_result.into());
+    }
+
+    match value.to_rational() {
+        Some(num) => Ok(exact_op(&num).into()),
+        None => unary_float_op(value, float_op),
+    }
+}
+
+fn unary_int_op<F, G>(value: &Value, int_op: F, big_op: G) -> Result<Value>
+where
+    F: Fn(i64) -> i64,
+    G: Fn(BigInt) -> BigInt,
+{
+    if let &Value::Integer(num) = value {
+        return Ok(int_op(num).into());
+    }
+
+    let num = value
+        .to_big_integer()
+        .ok_or_else(|| expected_integer(value))?;
+    Ok(big_op(num).into())
+}
+
+fn sqrt(value: &Value) -> Result<Value> {
+    if let Value::Complex(num) = value {
+        return Ok(num.sqrt().into());
+    }
+
     let num = value.as_number()?;
-    let num_result = f(num as i64) as f64;
-    Ok(num_result.into())
+    if num < 0.0 {
+        Ok(Complex64::new(0.0, (-num).sqrt()).into())
+    } else {
+        Ok(num.sqrt().into())
+    }
+}
+
+fn is_complex_pair(a: &Value, b: &Value) -> bool {
+    a.is_complex() || b.is_complex()
+}
+
+fn binary_complex_op<F: Fn(Complex64, Complex64) -> Complex64>(
+    a: &Value,
+    b: &Value,
+    f: F,
+) -> Result<Value> {
+    let num_a = a.to_complex().ok_or_else(|| expected("number", a))?;
+    let num_b = b.to_complex().ok_or_else(|| expected("number", b))?;
+    Ok(f(num_a, num_b).into())
 }
 
 fn binary_float_op<F: Fn(f64, f64) -> f64>(a: &Value, b: &Value, f: F) -> Result<Value> {
@@ -97,16 +198,296 @@ fn binary_float_op<F: Fn(f64, f64) -> f64>(a: &Value, b: &Value, f: F) -> Result
     Ok(num_result.into())
 }
 
-#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
-fn binary_int_op<F: Fn(i64, i64) -> i64>(a: &Value, b: &Value, f: F) -> Result<Value> {
-    let num_a = a.as_number()?;
-    let num_b = b.as_number()?;
-    let num_result = f(num_a as i64, num_b as i64) as f64;
-    Ok(num_result.into())
+fn binary_num_op<F, G, H, I>(
+    a: &Value,
+    b: &Value,
+    int_op: F,
+    big_op: G,
+    ratio_op: H,
+    float_op: I,
+) -> Result<Value>
+where
+    F: Fn(i64, i64) -> Option<i64>,
+    G: Fn(&BigInt, &BigInt) -> BigInt,
+    H: Fn(&BigRational, &BigRational) -> BigRational,
+    I: Fn(f64, f64) -> f64,
+{
+    if let (&Value::Integer(num_a), &Value::Integer(num_b)) = (a, b)
+        && let Some(num_result) = int_op(num_a, num_b)
+    {
+        return Ok(num_result.into());
+    }
+
+    if let (Some(num_a), Some(num_b)) = (a.to_big_integer(), b.to_big_integer()) {
+        return Ok(big_op(&num_a, &num_b).into());
+    }
+
+    if let (Some(num_a), Some(num_b)) = (a.to_rational(), b.to_rational()) {
+        return Ok(ratio_op(&num_a, &num_b).into());
+    }
+
+    binary_float_op(a, b, float_op)
+}
+
+fn binary_int_op<F, G>(a: &Value, b: &Value, int_op: F, big_op: G) -> Result<Value>
+where
+    F: Fn(i64, i64) -> i64,
+    G: Fn(&BigInt, &BigInt) -> BigInt,
+{
+    if let (&Value::Integer(num_a), &Value::Integer(num_b)) = (a, b) {
+        return Ok(int_op(num_a, num_b).into());
+    }
+
+    let num_a = a.to_big_integer().ok_or_else(|| expected_integer(a))?;
+    let num_b = b.to_big_integer().ok_or_else(|| expected_integer(b))?;
+    Ok(big_op(&num_a, &num_b).into())
+}
+
+fn div(a: &Value, b: &Value) -> Result<Value> {
+    if a.is_exact() && *b == Value::Integer(0) {
+        return Err(division_by_zero());
+    }
+
+    if let (&Value::Integer(num_a), &Value::Integer(num_b)) = (a, b)
+        && num_a.checked_rem(num_b) == Some(0)
+        && let Some(num_result) = num_a.checked_div(num_b)
+    {
+        return Ok(num_result.into());
+    }
+
+    match (a.to_rational(), b.to_rational()) {
+        (Some(num_a), Some(num_b)) => Ok((num_a / num_b).into()),
+        _ => binary_float_op(a, b, ops::Div::div),
+    }
+}
+
+fn rem(a: &Value, b: &Value) -> Result<Value> {
+    if a.is_exact() && *b == Value::Integer(0) {
+        return Err(division_by_zero());
+    }
+
+    binary_num_op(
+        a,
+        b,
+        i64::checked_rem,
+        |x, y| x % y,
+        |x, y| x % y,
+        ops::Rem::rem,
+    )
+}
+
+// Exact results are bounded, since a single instruction can't be stopped by limits or an
+// interrupt while it computes.
+const MAX_RESULT_BITS: u64 = 1 << 22;
+
+fn pow(a: &Value, b: &Value) -> Result<Value> {
+    let (Some(base), &Value::Integer(exp)) = (a.to_rational(), b) else {
+        return binary_float_op(a, b, f64::powf);
+    };
+
+    let exp_magnitude =
+        u32::try_from(exp.unsigned_abs()).map_err(|_| anyhow!("exponent {exp} is too large"))?;
+
+    if let &Value::Integer(num_a) = a
+        && exp >= 0
+        && let Some(num_result) = num_a.checked_pow(exp_magnitude)
+    {
+        return Ok(num_result.into());
+    }
+
+    // Powers of 0, 1 and -1 stay small, anything else grows by at least a bit per step.
+    let bits = base.numer().bits().max(base.denom().bits());
+    if bits > 1 && bits.saturating_mul(u64::from(exp_magnitude)) > MAX_RESULT_BITS {
+        return Err(anyhow!("result of raising {a} to {exp} is too large"));
+    }
+
+    let numer = base.numer().pow(exp_magnitude);
+    let denom = base.denom().pow(exp_magnitude);
+    if exp >= 0 {
+        Ok(BigRational::new(numer, denom).into())
+    } else if numer.is_zero() {
+        Err(division_by_zero())
+    } else {
+        Ok(BigRational::new(denom, numer).into())
+    }
+}
+
+fn shift_amount(value: &Value) -> Result<usize> {
+    let amount = value.as_integer()?;
+    usize::try_from(amount).map_err(|_| anyhow!("invalid shift amount {value}"))
+}
+
+fn shl(a: &Value, b: &Value) -> Result<Value> {
+    if let (&Value::Integer(num_a), &Value::Integer(num_b)) = (a, b)
+        && let Ok(shift) = u32::try_from(num_b)
+        && let Some(num_result) = num_a.checked_shl(shift)
+        && num_result >> shift == num_a
+    {
+        return Ok(num_result.into());
+    }
+
+    let num = a.to_big_integer().ok_or_else(|| expected_integer(a))?;
+    let shift = shift_amount(b)?;
+    if !num.is_zero() && num.bits().saturating_add(shift as u64) > MAX_RESULT_BITS {
+        return Err(anyhow!(
+            "result of shifting {a} left by {shift} is too large"
+        ));
+    }
+    Ok((num << shift).into())
+}
+
+fn shr(a: &Value, b: &Value) -> Result<Value> {
+    let num = a.to_big_integer().ok_or_else(|| expected_integer(a))?;
+    Ok((num >> shift_amount(b)?).into())
+}
+
+fn num_eq(a: &Value, b: &Value) -> bool {
+    if a.is_number() && b.is_number() {
+        a.numeric_cmp(b) == Some(Ordering::Equal)
+    } else {
+        a == b
+    }
 }
 
 #[allow(clippy::unnecessary_wraps)]
-fn bool_op<F: Fn(&Value, &Value) -> bool>(a: &Value, b: &Value, f: F) -> Result<Value> {
-    let bool_result = f(a, b);
-    Ok(bool_result.into())
+fn cmp_op<F: Fn(Ordering) -> bool>(a: &Value, b: &Value, f: F) -> Result<Value> {
+    let ordering = if a.is_number() && b.is_number() {
+        a.numeric_cmp(b)
+    } else {
+        Some(a.cmp(b))
+    };
+    Ok(ordering.is_some_and(f).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Value, op::Binary, parser};
+
+    fn parse_value(s: &str) -> Value {
+        parser::parse(s, parser::value()).unwrap()
+    }
+
+    #[test]
+    fn integer_arithmetic() {
+        let a = Value::from(i64::MAX - 1);
+        assert_eq!(
+            Value::from(i64::MAX),
+            Binary::Add.apply(&a, &1.into()).unwrap()
+        );
+        assert_eq!(
+            parse_value("9223372036854775808"),
+            Binary::Add.apply(&a, &2.into()).unwrap()
+        );
+        assert_eq!(
+            a,
+            Binary::Sub
+                .apply(&Binary::Add.apply(&a, &2.into()).unwrap(), &2.into())
+                .unwrap()
+        );
+
+        assert_eq!(
+            Value::from(3),
+            Binary::Div.apply(&6.into(), &2.into()).unwrap()
+        );
+        assert!(Binary::Div.apply(&1.into(), &0.into()).is_err());
+        assert_eq!(
+            parse_value("9223372036854775808"),
+            Binary::Div.apply(&i64::MIN.into(), &(-1).into()).unwrap()
+        );
+        assert!(Binary::Mod.apply(&1.into(), &0.into()).is_err());
+
+        assert_eq!(
+            Value::from(1024),
+            Binary::Pow.apply(&2.into(), &10.into()).unwrap()
+        );
+        assert_eq!(
+            parse_value("1267650600228229401496703205376"),
+            Binary::Pow.apply(&2.into(), &100.into()).unwrap()
+        );
+        assert!(Binary::Pow.apply(&2.into(), &100_000_000.into()).is_err());
+        assert_eq!(
+            Value::from(1),
+            Binary::Pow
+                .apply(&(-1).into(), &100_000_000.into())
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn rational_arithmetic() {
+        let third = Binary::Div.apply(&1.into(), &3.into()).unwrap();
+        assert_eq!(parse_value("1/3"), third);
+        assert_eq!("1/3", third.to_string());
+
+        let two_thirds = Binary::Add.apply(&third, &third).unwrap();
+        assert_eq!(parse_value("2/3"), two_thirds);
+        assert_eq!(
+            Value::from(1),
+            Binary::Add.apply(&two_thirds, &third).unwrap()
+        );
+
+        assert_eq!(
+            parse_value("1/2"),
+            Binary::Pow.apply(&2.into(), &(-1).into()).unwrap()
+        );
+        assert_eq!(
+            parse_value("4/9"),
+            Binary::Pow.apply(&two_thirds, &2.into()).unwrap()
+        );
+        assert!(Binary::Pow.apply(&0.into(), &(-1).into()).is_err());
+
+        assert_eq!(
+            Value::from(true),
+            Binary::Lt.apply(&third, &0.34.into()).unwrap()
+        );
+        assert_eq!(
+            Value::from(true),
+            Binary::Gt.apply(&two_thirds, &third).unwrap()
+        );
+    }
+
+    #[test]
+    fn mixed_arithmetic() {
+        assert_eq!(
+            Value::from(3.5),
+            Binary::Add.apply(&1.into(), &2.5.into()).unwrap()
+        );
+        assert_eq!(
+            Value::from(3.5),
+            Binary::Div.apply(&7.0.into(), &2.into()).unwrap()
+        );
+        assert_eq!(
+            Value::from(true),
+            Binary::Eq.apply(&1.into(), &1.0.into()).unwrap()
+        );
+        assert_eq!(
+            Value::from(true),
+            Binary::Lt.apply(&1.into(), &1.5.into()).unwrap()
+        );
+    }
+
+    #[test]
+    fn bitwise() {
+        let big = Value::from(1 << 60 | 1);
+        assert_eq!(big, Binary::Or.apply(&big, &1.into()).unwrap());
+        assert_eq!(
+            Value::from(1 << 62),
+            Binary::Shl.apply(&1.into(), &62.into()).unwrap()
+        );
+        assert_eq!(
+            parse_value("9223372036854775808"),
+            Binary::Shl.apply(&1.into(), &63.into()).unwrap()
+        );
+        assert_eq!(
+            Value::from(-1),
+            Binary::Shr.apply(&(-1).into(), &100.into()).unwrap()
+        );
+        assert!(Binary::And.apply(&1.5.into(), &1.into()).is_err());
+        assert!(Binary::Shl.apply(&1.into(), &(-1).into()).is_err());
+        assert!(Binary::Shl.apply(&1.into(), &100_000_000.into()).is_err());
+        assert_eq!(
+            Value::from(0),
+            Binary::Shl.apply(&0.into(), &100_000_000.into()).unwrap()
+        );
+    }
 }
@@ -0,0 +1,62 @@
+use crate::{Symbol, Value};
+
+// The test at the root of a compiled pattern. Sub-patterns are matched against values taken
+// apart by `Inst::Item`, `Inst::Rest` and `Inst::Key` once the test has passed.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Shape {
+    Equal(Value),
+    Cons,
+    // Exactly `len` items, or at least `len` when the pattern has a rest.
+    Vector { len: u16, rest: bool },
+    Compound(Symbol, u16),
+    // A map containing every key.
+    Map(Vec<Value>),
+}
+
+impl Shape {
+    pub fn matches(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Shape::Equal(expected), _) => expected == value,
+            (Shape::Cons, Value::Compound(cons)) => cons.is_cons(),
+            (&Shape::Vector { len, rest }, Value::Vector(values)) => {
+                let len = usize::from(len);
+                if rest {
+                    values.len() >= len
+                } else {
+                    values.len() == len
+                }
+            }
+            (&Shape::Compound(type_, len), Value::Compound(compound)) => {
+                compound.type_ == type_ && compound.len() == usize::from(len)
+            }
+            (Shape::Map(keys), Value::Map(map)) => keys.iter().all(|key| map.get(key).is_some()),
+            _ => false,
+        }
+    }
+
+    // Whether no value passes both tests. This is conservative: false means "not known to
+    // exclude".
+    pub fn excludes(&self, other: &Shape) -> bool {
+        match (self, other) {
+            (Shape::Equal(a), Shape::Equal(b)) => a != b,
+            (Shape::Equal(value), shape) | (shape, Shape::Equal(value)) => !shape.matches(value),
+            (Shape::Cons, Shape::Cons) | (Shape::Map(_), Shape::Map(_)) => false,
+            (
+                &Shape::Vector { len, rest },
+                &Shape::Vector {
+                    len: other_len,
+                    rest: other_rest,
+                },
+            ) => match (rest, other_rest) {
+                (false, false) => len != other_len,
+                (false, true) => len < other_len,
+                (true, false) => other_len < len,
+                (true, true) => false,
+            },
+            (&Shape::Compound(type_, len), &Shape::Compound(other_type, other_len)) => {
+                type_ != other_type || len != other_len
+            }
+            _ => true,
+        }
+    }
+}
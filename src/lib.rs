@@ -5,28 +5,37 @@ mod arity;
 mod builtin;
 mod compiler;
 mod convert;
+mod debugger;
+pub mod egraph;
 mod env;
 mod error;
 mod expr;
 mod function;
 mod instruction;
 mod iterator;
+pub mod logic;
 mod module;
 mod parser;
+mod rewrite;
 mod special;
 mod symbol;
+pub mod symbolic;
 mod value;
 mod vm;
 
 pub use arity::Arity;
 pub use convert::try_as_array;
+pub use debugger::{Breakpoint, Debugger, Pause};
 pub use env::Env;
 pub use error::{Error, Result};
-pub use expr::Expr;
-pub use function::FnId;
-pub use instruction::{Inst, op};
+pub use expr::{Clause, Expr, Pattern};
+pub use function::{FnId, FnRef};
+pub use instruction::{Inst, Shape, op};
 pub use iterator::ResultIterator;
 pub use module::Module;
+pub use rewrite::{Rewrite, Rewriter, Rule, Step, Strategy};
 pub use symbol::Symbol;
-pub use value::Value;
-pub use vm::VM;
+pub use value::{Pretty, Value, intern};
+pub use vm::{
+    Execution, FnProfile, Frame, Interrupt, Limits, Profiler, Status, Tracer, VM, WriteTracer,
+};
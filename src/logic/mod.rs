@@ -0,0 +1,192 @@
+mod native;
+mod syntax;
+
+use std::cell::Cell;
+
+use crate::{Symbol, Value, value::Map};
+
+pub(crate) use native::define_all;
+pub(crate) use syntax::{try_from_conde, try_from_fresh, try_from_run, try_from_run_all};
+
+// Bindings from logic variables to terms. Bound terms may themselves contain variables, so
+// lookups go through `walk`.
+pub type Substitution = Map;
+
+thread_local! {
+    static NEXT_VAR: Cell<u64> = const { Cell::new(0) };
+}
+
+#[must_use]
+pub fn fresh_var() -> Value {
+    NEXT_VAR.with(|next| {
+        let id = next.get();
+        next.set(id + 1);
+        Value::LogicVar(id)
+    })
+}
+
+// Follows variable bindings until reaching an unbound variable or a non-variable term.
+#[must_use]
+pub fn walk(value: &Value, s: &Substitution) -> Value {
+    let mut value = value;
+    while let Value::LogicVar(_) = value
+        && let Some(bound) = s.get(value)
+    {
+        value = bound;
+    }
+    value.clone()
+}
+
+// Like `walk`, but also substitutes inside compounds and vectors.
+#[must_use]
+pub fn walk_all(value: &Value, s: &Substitution) -> Value {
+    let value = walk(value, s);
+    match &value {
+        Value::Compound(compound) => Value::compound(
+            compound.type_,
+            compound.values.iter().map(|v| walk_all(v, s)).collect(),
+        ),
+        Value::Vector(values) => values
+            .iter()
+            .map(|v| walk_all(v, s))
+            .collect::<im::Vector<_>>()
+            .into(),
+        _ => value,
+    }
+}
+
+fn occurs(var: &Value, value: &Value, s: &Substitution) -> bool {
+    let value = walk(value, s);
+    match &value {
+        Value::LogicVar(_) => *var == value,
+        Value::Compound(compound) => compound.values.iter().any(|v| occurs(var, v, s)),
+        Value::Vector(values) => values.iter().any(|v| occurs(var, v, s)),
+        _ => false,
+    }
+}
+
+fn extend(var: Value, value: Value, s: &Substitution) -> Option<Substitution> {
+    if occurs(&var, &value, s) {
+        None
+    } else {
+        let mut s = s.clone();
+        s.insert(var, value);
+        Some(s)
+    }
+}
+
+// First-order unification with occurs check. Compounds unify when their types and arities
+// agree, vectors when their lengths agree, and any other values only when they are equal.
+#[must_use]
+pub fn unify(u: &Value, v: &Value, s: Substitution) -> Option<Substitution> {
+    let u = walk(u, &s);
+    let v = walk(v, &s);
+    match (&u, &v) {
+        _ if u == v => Some(s),
+        (Value::LogicVar(_), _) => extend(u, v, &s),
+        (_, Value::LogicVar(_)) => extend(v, u, &s),
+        (Value::Compound(us), Value::Compound(vs))
+            if us.type_ == vs.type_ && us.values.len() == vs.values.len() =>
+        {
+            us.values
+                .iter()
+                .zip(&vs.values)
+                .try_fold(s, |s, (u, v)| unify(u, v, s))
+        }
+        (Value::Vector(us), Value::Vector(vs)) if us.len() == vs.len() => us
+            .iter()
+            .zip(vs.iter())
+            .try_fold(s, |s, (u, v)| unify(u, v, s)),
+        _ => None,
+    }
+}
+
+// Substitutes all bound variables and names the remaining ones `_.0`, `_.1`, ... in order of
+// appearance.
+#[must_use]
+pub fn reify(value: &Value, s: &Substitution) -> Value {
+    fn name_vars(value: &Value, names: &mut Substitution) {
+        match value {
+            Value::LogicVar(_) if !names.contains_key(value) => {
+                let name = Symbol::new(format!("_.{}", names.len()));
+                names.insert(value.clone(), name.into());
+            }
+            Value::Compound(compound) => {
+                for value in &compound.values {
+                    name_vars(value, names);
+                }
+            }
+            Value::Vector(values) => {
+                for value in values.iter() {
+                    name_vars(value, names);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let value = walk_all(value, s);
+    let mut names = Substitution::default();
+    name_vars(&value, &mut names);
+    walk_all(&value, &names)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Module, Symbol, VM, Value};
+
+    use super::{Substitution, fresh_var, reify, unify, walk};
+
+    #[test]
+    fn unification() {
+        let x = fresh_var();
+        let y = fresh_var();
+        let term = |type_: &str, values: Vec<Value>| Value::compound(Symbol::new(type_), values);
+
+        let s = unify(
+            &term("f", vec![x.clone(), term("g", vec![y.clone()])]),
+            &term("f", vec![Value::from(1), term("g", vec![x.clone()])]),
+            Substitution::default(),
+        )
+        .unwrap();
+        assert_eq!(Value::from(1), walk(&y, &s));
+        assert_eq!(
+            term("g", vec![Value::from(1)]),
+            reify(&term("g", vec![y.clone()]), &s)
+        );
+
+        assert!(unify(&x, &term("f", vec![x.clone()]), Substitution::default()).is_none());
+        assert!(unify(&term("f", vec![x.clone()]), &term("g", vec![y.clone()]), s).is_none());
+        assert_eq!(
+            "[_.0 _.1 _.0]",
+            reify(&Value::vector([x.clone(), y, x]), &Substitution::default()).to_string()
+        );
+    }
+
+    #[test]
+    fn relations() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+        let mut eval = |input: &str| module.eval_str(input).unwrap().to_string();
+
+        assert_eq!("(1 2)", eval("(run* [q] (conde [(== q 1)] [(== q 2)]))"));
+        assert_eq!(
+            "([1 _.0])",
+            eval("(run 1 [q] (fresh [x y] (== q [x y]) (== x 1)))")
+        );
+        assert_eq!(
+            "((nil (1 2)) ((1) (2)) ((1 2) nil))",
+            eval(
+                "(letfn [(appendo [l s out]
+                           (conde
+                             [(== l nil) (== s out)]
+                             [(fresh [a d res]
+                                (== l (compound 'cons a d))
+                                (== out (compound 'cons a res))
+                                (appendo d s res))]))]
+                   (run* [q] (fresh [x y] (== q (list x y)) (appendo x y '(1 2)))))"
+            )
+        );
+        assert_eq!("nil", eval("(run* [q] (== q 1) (== q 2))"));
+    }
+}
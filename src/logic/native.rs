@@ -0,0 +1,111 @@
+use anyhow::anyhow;
+
+use crate::{Expr, Module, Result, Symbol, Value, parser, try_as_array};
+
+use super::{Substitution, fresh_var, reify, unify, walk, walk_all};
+
+pub(super) const LVAR: &str = "kanren.lvar";
+pub(super) const CONJ: &str = "kanren.conj";
+pub(super) const DISJ: &str = "kanren.disj";
+pub(super) const RUN: &str = "kanren.run";
+
+// A copy to extend, sharing the map's nodes.
+fn substitution(value: &Value) -> Result<Substitution> {
+    Ok((*value.as_map()?).clone())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn lvar(_values: &[Value]) -> Result<Value> {
+    Ok(fresh_var())
+}
+
+fn is_lvar(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    Ok(matches!(value, Value::LogicVar(_)).into())
+}
+
+// `(unify u v [s])` returns the extended substitution, or false if `u` and `v` don't unify.
+fn unify_(values: &[Value]) -> Result<Value> {
+    let (u, v, s) = match values {
+        [u, v] => (u, v, Substitution::default()),
+        [u, v, s] => (u, v, substitution(s)?),
+        _ => return Err(anyhow!("expected 2 or 3 arguments, got {}", values.len())),
+    };
+    Ok(unify(u, v, s).map_or_else(Value::false_, Value::from))
+}
+
+fn walk_(values: &[Value]) -> Result<Value> {
+    let [value, s] = try_as_array(values)?;
+    let s = s.as_map()?;
+    Ok(walk(value, &s))
+}
+
+fn walk_all_(values: &[Value]) -> Result<Value> {
+    let [value, s] = try_as_array(values)?;
+    let s = s.as_map()?;
+    Ok(walk_all(value, &s))
+}
+
+fn reify_(values: &[Value]) -> Result<Value> {
+    let [value, s] = try_as_array(values)?;
+    let s = s.as_map()?;
+    Ok(reify(value, &s))
+}
+
+// Goals are closures from a substitution to a stream of substitutions. A stream is nil, a
+// list whose tail is a stream, or a thunk producing a stream; thunks let `mplus` interleave
+// infinite streams.
+const PRELUDE: &str = "
+(letfn [(mplus [a b]
+          (match a
+            nil b
+            (x . xs) (compound 'cons x (mplus xs b))
+            _ (fn [] (mplus b (a)))))
+        (bind [st g]
+          (match st
+            nil nil
+            (x . xs) (mplus (g x) (bind xs g))
+            _ (fn [] (bind (st) g))))
+        (take [n st]
+          (if (equal? n 0)
+            nil
+            (match st
+              nil nil
+              (x . xs) (compound 'cons x (take (if (equal? n nil) nil ($sub n 1)) xs))
+              _ (take n (st)))))
+        (reify-all [q ss]
+          (match ss
+            nil nil
+            (s . rest) (compound 'cons (reify q s) (reify-all q rest))))
+        (== [u v]
+          (fn [s]
+            (let s2 (unify u v s)
+              (if s2 (list s2) nil))))
+        (conj [g1 g2] (fn [s] (bind (g1 s) g2)))
+        (disj [g1 g2] (fn [s] (mplus (g1 s) (g2 s))))
+        (solve [n q g] (reify-all q (take n (g {}))))]
+  [== conj disj solve (fn [s] (list s)) (fn [s] nil)])";
+
+fn define_prelude(module: &mut Module) {
+    let value = parser::parse(PRELUDE, parser::value()).expect("logic prelude should parse");
+    let expr = Expr::try_from(&value).expect("logic prelude should compile");
+    let prelude = module
+        .vm
+        .eval_builtin(&module.env, &expr)
+        .expect("logic prelude should evaluate");
+    let names = ["==", CONJ, DISJ, RUN, "succeed", "fail"];
+    for (name, value) in names.into_iter().zip(prelude.iter()) {
+        module.set(Symbol::new(name), value.clone());
+    }
+}
+
+pub fn define_all(module: &mut Module) {
+    module.set_native("lvar", lvar, 0);
+    module.set_native(LVAR, lvar, 0);
+    module.set_native("lvar?", is_lvar, 1);
+    module.set_native("unify", unify_, 2..);
+    module.set_native("walk", walk_, 2);
+    module.set_native("walk*", walk_all_, 2);
+    module.set_native("reify", reify_, 2);
+    define_prelude(module);
+}
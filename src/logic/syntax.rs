@@ -0,0 +1,106 @@
+use anyhow::anyhow;
+
+use crate::{Expr, Result, ResultIterator, Value};
+
+use super::native::{CONJ, DISJ, LVAR, RUN};
+
+fn call(function: &str, args: impl IntoIterator<Item = Value>) -> Value {
+    let mut values = vec![Value::symbol(function)];
+    values.extend(args);
+    Value::list(values)
+}
+
+// Folds goals with a binary combinator, right to left.
+fn fold(function: &str, goals: &[Value], what: &str) -> Result<Value> {
+    let (last, rest) = goals
+        .split_last()
+        .ok_or_else(|| anyhow!("`{what}` expects at least one goal"))?;
+    Ok(rest.iter().rev().fold(last.clone(), |acc, goal| {
+        call(function, [goal.clone(), acc])
+    }))
+}
+
+// Wraps a goal so that it is only run when the stream is forced, which is what lets recursive
+// relations interleave instead of diverging.
+fn delay(prefix: &str, goal: Value) -> Value {
+    let s = Value::symbol(format!("{prefix}.s"));
+    let body = Value::list([goal, s.clone()]);
+    Value::list([
+        Value::symbol("fn"),
+        Value::vector([s]),
+        Value::list([
+            Value::symbol("fn"),
+            Value::vector(Vec::<Value>::new()),
+            body,
+        ]),
+    ])
+}
+
+fn fresh_bindings(vars_value: &Value, what: &str) -> Result<Vec<Value>> {
+    let vars: Vec<Value> = vars_value.as_vector()?.iter().cloned().collect();
+    if let Some(var) = vars.iter().find(|var| !matches!(var, Value::Symbol(_))) {
+        return Err(anyhow!("`{what}` expects variables, got {var}"));
+    }
+
+    Ok(vars
+        .into_iter()
+        .flat_map(|var| [var, call(LVAR, [])])
+        .collect())
+}
+
+fn let_(bindings: Vec<Value>, body: Value) -> Value {
+    let mut values = vec![Value::symbol("let")];
+    values.extend(bindings);
+    values.push(body);
+    Value::list(values)
+}
+
+// `(fresh [x ...] goal ...)`
+pub fn try_from_fresh(values: &[Value]) -> Result<Expr> {
+    let [vars_value, goals @ ..] = values else {
+        return Err(anyhow!("malformed `fresh` expression"));
+    };
+
+    let bindings = fresh_bindings(vars_value, "fresh")?;
+    let goal = let_(bindings, fold(CONJ, goals, "fresh")?);
+    Expr::try_from(&delay("fresh", goal))
+}
+
+// `(conde [goal ...] ...)`
+pub fn try_from_conde(values: &[Value]) -> Result<Expr> {
+    let clauses: Vec<Value> = values
+        .iter()
+        .map(|clause| {
+            let goals: Vec<Value> = clause.as_vector()?.iter().cloned().collect();
+            fold(CONJ, &goals, "conde")
+        })
+        .try_collect()?;
+    Expr::try_from(&delay("conde", fold(DISJ, &clauses, "conde")?))
+}
+
+fn run(n: Value, vars_value: &Value, goals: &[Value]) -> Result<Expr> {
+    let bindings = fresh_bindings(vars_value, "run")?;
+    let vars: Vec<Value> = bindings.iter().step_by(2).cloned().collect();
+    let query = match vars.as_slice() {
+        [var] => var.clone(),
+        _ => call("list", vars),
+    };
+    let body = call(RUN, [n, query, fold(CONJ, goals, "run")?]);
+    Expr::try_from(&let_(bindings, body))
+}
+
+// `(run n [q ...] goal ...)`
+pub fn try_from_run(values: &[Value]) -> Result<Expr> {
+    let [n, vars_value, goals @ ..] = values else {
+        return Err(anyhow!("malformed `run` expression"));
+    };
+    run(n.clone(), vars_value, goals)
+}
+
+// `(run* [q ...] goal ...)`
+pub fn try_from_run_all(values: &[Value]) -> Result<Expr> {
+    let [vars_value, goals @ ..] = values else {
+        return Err(anyhow!("malformed `run*` expression"));
+    };
+    run(Value::nil(), vars_value, goals)
+}
@@ -1,11 +1,183 @@
-use jymbol::{Error, Module, VM};
+use std::io::{self, BufRead, StdinLock, Write};
 
-fn main() -> Result<(), Error> {
+use anyhow::anyhow;
+use jymbol::{
+    Breakpoint, Debugger, Error, Execution, Expr, FnRef, Frame, Interrupt, Module, Pause, Pretty,
+    Result, VM, Value,
+};
+
+type Lines<'a> = io::Lines<StdinLock<'a>>;
+
+const HELP: &str = "\
+:load <path>      evaluate the forms in a file
+:break <line>     pause before the code of a source line
+:break <name>     pause when the function bound to <name> is called
+:clear            remove all breakpoints
+While paused:
+:continue, :c     run to the next breakpoint
+:step, :s         step one instruction, into calls
+:next, :n         step one instruction, over calls
+:out, :o          run until the current function returns
+:frames           list the call frames
+:locals [frame]   print the locals of a frame, the innermost by default
+:abort            stop the evaluation
+<expr>            evaluate <expr> in the innermost frame";
+
+fn read_line(lines: &mut Lines<'_>, prompt: &str) -> Result<Option<String>> {
+    print!("{prompt}");
+    io::stdout().flush()?;
+    lines.next().transpose().map_err(Error::from)
+}
+
+struct Repl<'a> {
+    module: Module<'a>,
+    debugger: Debugger,
+    interrupt: Interrupt,
+}
+
+impl Repl<'_> {
+    fn command(&mut self, lines: &mut Lines<'_>, line: &str) -> Result<()> {
+        let (command, arg) = line.split_once(' ').unwrap_or((line, ""));
+        let arg = arg.trim();
+        match command {
+            ":help" => println!("{HELP}"),
+            ":load" => {
+                let source = std::fs::read_to_string(arg)?;
+                self.eval_source(lines, &source)?;
+            }
+            ":break" => {
+                let breakpoint = match arg.parse() {
+                    Ok(line) => Breakpoint::Line(line),
+                    Err(_) => {
+                        let value = self.module.env.get(arg.into())?;
+                        self.debugger.break_on(&value)?
+                    }
+                };
+                self.debugger.add_breakpoint(breakpoint);
+            }
+            ":clear" => self.debugger.clear_breakpoints(),
+            _ => return Err(anyhow!("unknown command {command}, try :help")),
+        }
+        Ok(())
+    }
+
+    fn eval_source(&mut self, lines: &mut Lines<'_>, source: &str) -> Result<()> {
+        for value in Module::read_source(source)? {
+            // A Ctrl-C pressed at the prompt shouldn't cancel the next evaluation.
+            self.interrupt.reset();
+            let value = self.eval(lines, &value)?;
+            println!("{}", Pretty::new().render(&value));
+        }
+        Ok(())
+    }
+
+    fn eval(&mut self, lines: &mut Lines<'_>, value: &Value) -> Result<Value> {
+        let env = self.module.env.clone();
+        let mut execution = self.module.start(value)?;
+        if self.debugger.breakpoints().next().is_none() {
+            return execution.resume();
+        }
+
+        let mut pause = self.debugger.continue_(&mut execution)?;
+        let mut moved = true;
+        loop {
+            if let Pause::Finished(value) = pause {
+                return Ok(value);
+            }
+            if moved {
+                print_location(&execution);
+            }
+            moved = false;
+
+            let Some(line) = read_line(lines, "debug> ")? else {
+                return Err(anyhow!("aborted"));
+            };
+            let (command, arg) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let top = execution.frames().len() - 1;
+            pause = match command {
+                "" => continue,
+                ":continue" | ":c" => self.debugger.continue_(&mut execution)?,
+                ":step" | ":s" => self.debugger.step_in(&mut execution)?,
+                ":next" | ":n" => self.debugger.step_over(&mut execution)?,
+                ":out" | ":o" => self.debugger.step_out(&mut execution)?,
+                ":abort" => return Err(anyhow!("aborted")),
+                ":frames" => {
+                    for (i, frame) in execution.frames().iter().enumerate().rev() {
+                        println!("{i}: {}", execution.vm().fn_name(frame.fn_ref()));
+                    }
+                    continue;
+                }
+                ":locals" => {
+                    let index = if arg.is_empty() { Ok(top) } else { arg.parse() };
+                    match index {
+                        Ok(index) => {
+                            for (name, value) in execution.frame_locals(index) {
+                                println!("{name} = {}", Pretty::new().render(&value));
+                            }
+                        }
+                        Err(err) => eprintln!("error: {err}"),
+                    }
+                    continue;
+                }
+                _ => {
+                    let result = Module::read_source(&line).and_then(|values| {
+                        let [value] = <[Value; 1]>::try_from(values)
+                            .map_err(|_| anyhow!("expected one expression"))?;
+                        execution.eval_in_frame(top, &env, &Expr::try_from(&value)?)
+                    });
+                    match result {
+                        Ok(value) => println!("{}", Pretty::new().render(&value)),
+                        Err(err) => eprintln!("error: {err}"),
+                    }
+                    continue;
+                }
+            };
+            moved = true;
+        }
+    }
+}
+
+fn print_location(execution: &Execution<'_>) {
+    let Some(Frame::Compiled(frame)) = execution.current_frame() else {
+        return;
+    };
+
+    let name = execution.vm().fn_name(FnRef::Compiled(frame.fn_id));
+    match execution.vm().line_at(frame.fn_id, frame.pc) {
+        Some(line) => println!("paused in {name} at line {line}"),
+        None => println!("paused in {name} at pc {}", frame.pc),
+    }
+}
+
+fn main() -> Result<()> {
     let mut vm = VM::new();
-    let mut module = Module::new(&mut vm);
 
-    let input = r#""It works!""#;
-    let value = module.eval_str(input)?;
-    println!("{value}");
+    // Ctrl-C interrupts the running evaluation rather than killing the REPL.
+    let interrupt = vm.interrupt_handle();
+    ctrlc::set_handler({
+        let interrupt = interrupt.clone();
+        move || interrupt.trigger()
+    })?;
+
+    let mut repl = Repl {
+        module: Module::new(&mut vm),
+        debugger: Debugger::new(),
+        interrupt,
+    };
+    let mut lines = io::stdin().lock().lines();
+    while let Some(line) = read_line(&mut lines, "> ")? {
+        let line = line.trim();
+        let result = if line.starts_with(':') {
+            repl.command(&mut lines, line)
+        } else if line.is_empty() {
+            Ok(())
+        } else {
+            repl.eval_source(&mut lines, line)
+        };
+
+        if let Err(err) = result {
+            eprintln!("error: {err}");
+        }
+    }
     Ok(())
 }
@@ -1,4 +1,6 @@
-use crate::{Arity, Env, Expr, Result, Symbol, VM, Value, builtin, function::RawFn, parser};
+use crate::{
+    Arity, Env, Execution, Expr, FnRef, Result, Symbol, VM, Value, builtin, function::RawFn, parser,
+};
 
 #[derive(Debug)]
 pub struct Module<'a> {
@@ -18,7 +20,12 @@ impl<'a> Module<'a> {
     }
 
     pub fn set<S: Into<Symbol>>(&mut self, s: S, value: Value) {
-        self.env.insert(s.into(), value);
+        let sym = s.into();
+        if let Some(fn_ref) = FnRef::of(&value) {
+            self.vm.name_function(fn_ref, sym);
+        }
+
+        self.env.insert(sym, value);
     }
 
     pub fn set_native<S: Into<Symbol>, A: Into<Arity>>(&mut self, s: S, function: RawFn, arity: A) {
@@ -31,8 +38,20 @@ impl<'a> Module<'a> {
         self.vm.eval(&self.env, &expr)
     }
 
+    pub fn start(&mut self, value: &Value) -> Result<Execution<'_>> {
+        let expr: Expr = value.try_into()?;
+        self.vm.start(&self.env, &expr)
+    }
+
     pub fn eval_str<S: AsRef<str>>(&mut self, s: S) -> Result<Value> {
         let value = parser::parse(s, parser::value())?;
         self.eval(&value)
     }
+
+    // Reads every form in `s`, keeping the line each list starts on so code compiled from them
+    // can be paused with `Breakpoint::Line`.
+    pub fn read_source<S: AsRef<str>>(s: S) -> Result<Vec<Value>> {
+        let s = s.as_ref();
+        parser::parse(s, parser::source(s))
+    }
 }
@@ -1,14 +1,21 @@
 mod primitive;
 mod value;
 
-use chumsky::{Parser, prelude::Simple};
-pub use value::value;
+use chumsky::{Parser, error::SimpleReason, prelude::Simple};
+pub use value::{source, value};
 
 use crate::{Error, Result};
 
 #[must_use]
 pub fn collect_errors(errors: Vec<Simple<char>>) -> Error {
-    let error_strings: Vec<String> = errors.into_iter().map(|err| err.to_string()).collect();
+    // `Simple`'s `Display` ignores the reason, which would hide custom messages.
+    let error_strings: Vec<String> = errors
+        .into_iter()
+        .map(|err| match err.reason() {
+            SimpleReason::Custom(message) => message.clone(),
+            _ => err.to_string(),
+        })
+        .collect();
     Error::msg(error_strings.join("\n"))
 }
 
@@ -21,7 +28,9 @@ pub fn parse<T, S: AsRef<str>>(
 
 #[cfg(test)]
 mod tests {
-    use crate::{Result, Value, parser};
+    use proptest::prelude::*;
+
+    use crate::{Result, Symbol, Value, parser, value::arbitrary};
 
     fn parse_value<S: AsRef<str>>(s: S) -> Result<Value> {
         parser::parse(s, parser::value())
@@ -55,8 +64,36 @@ mod tests {
     }
 
     #[test]
-    fn number() {
-        assert_eq!(Value::from(0.0), parse_value("0").unwrap());
+    fn integer() {
+        assert_eq!(Value::from(0), parse_value("0").unwrap());
+        assert_eq!(Value::from(42), parse_value("42").unwrap());
+        assert_eq!(Value::from(-7), parse_value("-7").unwrap());
+        assert_eq!(
+            Value::from(i64::MAX),
+            parse_value("9223372036854775807").unwrap()
+        );
+
+        assert_eq!(
+            "-92233720368547758080",
+            parse_value("-92233720368547758080").unwrap().to_string()
+        );
+
+        assert!(parse_value("01").is_err());
+    }
+
+    #[test]
+    fn rational() {
+        assert_eq!("1/3", parse_value("1/3").unwrap().to_string());
+        assert_eq!("-1/2", parse_value("-2/4").unwrap().to_string());
+        assert_eq!(Value::from(2), parse_value("4/2").unwrap());
+
+        assert!(parse_value("1/0").is_err());
+        assert!(parse_value("1/-2").is_err());
+    }
+
+    #[test]
+    fn float() {
+        assert_eq!(Value::from(0.0), parse_value("0.0").unwrap());
         assert_eq!(Value::from(1.0), parse_value("1.0").unwrap());
         assert_eq!(Value::from(1.618_034), parse_value("1.618034").unwrap());
         assert_eq!(Value::from(-2e3), parse_value("-2e3").unwrap());
@@ -69,6 +106,19 @@ mod tests {
         assert!(parse_value("01").is_err());
     }
 
+    #[test]
+    fn complex() {
+        assert_eq!("1.0+2.0i", parse_value("1+2i").unwrap().to_string());
+        assert_eq!("-1.5-0.5i", parse_value("-1.5-1/2i").unwrap().to_string());
+        assert_eq!("0.0+3.0i", parse_value("3i").unwrap().to_string());
+        assert_eq!("2.0-1.0i", parse_value("2-i").unwrap().to_string());
+
+        let value = parse_value("1e3-2.5i").unwrap();
+        assert_eq!(value, parse_value(value.to_string()).unwrap());
+
+        assert_eq!(Value::symbol("+i"), parse_value("+i").unwrap());
+    }
+
     #[test]
     #[allow(clippy::manual_string_new)]
     fn string() {
@@ -137,7 +187,6 @@ mod tests {
     #[test]
     fn lists() {
         assert_eq!(Value::nil(), parse_value("()").unwrap());
-        assert_eq!(Value::nil(), parse_value("[]").unwrap());
         assert_eq!(Value::list([Value::nil()]), parse_value("(())").unwrap());
 
         assert!(parse_value("(").is_err());
@@ -146,4 +195,110 @@ mod tests {
         assert!(parse_value("(()").is_err());
         assert!(parse_value("([)]").is_err());
     }
+
+    #[test]
+    fn vectors() {
+        assert_eq!(Value::vector([]), parse_value("[]").unwrap());
+        assert_eq!(
+            Value::vector([Value::from(1), Value::list([Value::symbol("a")])]),
+            parse_value("[1 (a)]").unwrap()
+        );
+        assert_eq!("[1 [] (a)]", parse_value("[1 [] (a)]").unwrap().to_string());
+
+        assert!(parse_value("[").is_err());
+        assert!(parse_value("[)").is_err());
+    }
+
+    #[test]
+    fn maps_and_sets() {
+        assert_eq!(Value::map([]), parse_value("{}").unwrap());
+        assert_eq!(
+            Value::map([(Value::symbol("a"), Value::from(1))]),
+            parse_value("{a 1}").unwrap()
+        );
+        assert_eq!(Value::set([Value::from(1)]), parse_value("#{1 1}").unwrap());
+        assert_eq!(
+            "{a #{} b [c]}",
+            parse_value("{b [c] a #{}}").unwrap().to_string()
+        );
+
+        assert!(parse_value("{a}").is_err());
+        assert!(parse_value("#{").is_err());
+    }
+
+    #[test]
+    fn compounds() {
+        let point = Value::compound(Symbol::new("point"), vec![1.into(), Value::symbol("x")]);
+        assert_eq!(point, parse_value("(#point 1 x)").unwrap());
+        assert_eq!(point, parse_value("( #point 1 x )").unwrap());
+        assert_eq!(point, parse_value("#point(1 x)").unwrap());
+        assert_eq!(
+            Value::compound(Symbol::new("nil"), vec![]),
+            parse_value("#nil()").unwrap()
+        );
+        assert_eq!(
+            Value::list([1.into()]),
+            parse_value("(#cons 1 nil)").unwrap()
+        );
+        assert_eq!(Value::symbol("#point"), parse_value("#point").unwrap());
+
+        assert_eq!(
+            "#cons takes 2 arguments, got 1",
+            parse_value("#cons(1)").unwrap_err().to_string()
+        );
+        assert!(parse_value("(#quote)").is_err());
+        assert!(parse_value("(#unquote 1 2)").is_err());
+    }
+
+    #[test]
+    fn dotted_lists() {
+        let pair = Value::cons(1.into(), 2.into());
+        assert_eq!(pair, parse_value("(1 . 2)").unwrap());
+        assert_eq!("(1 . 2)", pair.to_string());
+
+        let dotted = Value::list_with_tail([1.into(), 2.into()], Value::symbol("rest"));
+        assert_eq!(dotted, parse_value("( 1 2 . rest )").unwrap());
+        assert_eq!("(1 2 . rest)", dotted.to_string());
+
+        assert_eq!(
+            Value::list([1.into(), 2.into()]),
+            parse_value("(1 . (2))").unwrap()
+        );
+        assert!(parse_value("(. 1)").is_err());
+        assert!(parse_value("(1 . 2 3)").is_err());
+    }
+
+    #[test]
+    fn chars_keywords_and_bytes() {
+        assert_eq!(Value::from('a'), parse_value("#\\a").unwrap());
+        assert_eq!(Value::from(' '), parse_value("#\\space").unwrap());
+        assert_eq!(Value::from('s'), parse_value("#\\s").unwrap());
+        assert_eq!(Value::from('\u{7f}'), parse_value("#\\u{7f}").unwrap());
+        assert_eq!(Value::keyword("key"), parse_value(":key").unwrap());
+        assert_eq!(
+            Value::from(b"a\"\xff".to_vec()),
+            parse_value("#b\"a\\\"\\xff\"").unwrap()
+        );
+        assert_eq!(
+            Value::list([Value::from(b"x".to_vec())]),
+            parse_value("(#b\"x\")").unwrap()
+        );
+
+        assert!(parse_value("#b\"\\x1\"").is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn display_round_trips(value in arbitrary::printable()) {
+            prop_assert_eq!(&value, &parse_value(value.to_string()).unwrap());
+        }
+    }
+
+    #[test]
+    fn display_numbers() {
+        assert_eq!("42", Value::from(42).to_string());
+        assert_eq!("42.0", Value::from(42.0).to_string());
+        assert_eq!("-0.5", Value::from(-0.5).to_string());
+        assert_eq!("1e100", Value::from(1e100).to_string());
+    }
 }
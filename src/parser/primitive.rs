@@ -1,4 +1,30 @@
 use chumsky::prelude::*;
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_rational::BigRational;
+use num_traits::Zero;
+
+pub fn integer() -> impl Parser<char, BigInt, Error = Simple<char>> {
+    just('-')
+        .or_not()
+        .chain::<char, _, _>(text::int(10))
+        .collect::<String>()
+        .map(|num_str| num_str.parse().unwrap())
+}
+
+pub fn rational() -> impl Parser<char, BigRational, Error = Simple<char>> {
+    integer()
+        .then_ignore(just('/'))
+        .then(text::int(10).map(|num_str: String| num_str.parse::<BigInt>().unwrap()))
+        .validate(|(numer, denom), span, emit| {
+            if denom.is_zero() {
+                emit(Simple::custom(span, format!("invalid rational: {numer}/0")));
+                BigRational::zero()
+            } else {
+                BigRational::new(numer, denom)
+            }
+        })
+}
 
 pub fn float() -> impl Parser<char, f64, Error = Simple<char>> {
     let frac = just('.').chain(text::digits(10));
@@ -8,11 +34,12 @@ pub fn float() -> impl Parser<char, f64, Error = Simple<char>> {
         .chain(just('+').or(just('-')).or_not())
         .chain(text::digits(10));
 
+    let frac_exp = frac.chain::<char, _, _>(exp.or_not().flatten()).or(exp);
+
     just('-')
         .or_not()
-        .chain(text::int(10))
-        .chain(frac.or_not().flatten())
-        .chain::<char, _, _>(exp.or_not().flatten())
+        .chain::<char, _, _>(text::int(10))
+        .chain::<char, _, _>(frac_exp)
         .collect::<String>()
         .validate(|num_str, span, emit| {
             num_str.parse().unwrap_or_else(|_| {
@@ -22,6 +49,77 @@ pub fn float() -> impl Parser<char, f64, Error = Simple<char>> {
         })
 }
 
+fn unsigned_real() -> impl Parser<char, f64, Error = Simple<char>> {
+    let frac = just('.').chain(text::digits(10));
+
+    let exp = just('e')
+        .or(just('E'))
+        .chain(just('+').or(just('-')).or_not())
+        .chain(text::digits(10));
+
+    let frac_exp = frac.chain::<char, _, _>(exp.or_not().flatten()).or(exp);
+    let ratio = just('/').chain(text::int(10));
+
+    text::int(10)
+        .chain::<char, _, _>(frac_exp.or(ratio).or_not().flatten())
+        .collect::<String>()
+        .validate(|num_str, span, emit| {
+            let num = match num_str.split_once('/') {
+                Some((numer, denom)) => {
+                    numer.parse::<f64>().unwrap_or(f64::NAN) / denom.parse().unwrap_or(f64::NAN)
+                }
+                None => num_str.parse().unwrap_or(f64::NAN),
+            };
+
+            if !num.is_finite() {
+                emit(Simple::custom(span, format!("invalid number: {num_str}")));
+            }
+
+            num
+        })
+}
+
+pub fn complex() -> impl Parser<char, Complex64, Error = Simple<char>> {
+    let sign = just('+').to(1.0).or(just('-').to(-1.0));
+    let real = || {
+        just('-')
+            .or_not()
+            .then(unsigned_real())
+            .map(|(sign, num)| if sign.is_some() { -num } else { num })
+    };
+    let imag = sign
+        .then(unsigned_real().or_not())
+        .then_ignore(just('i'))
+        .map(|(sign, num)| sign * num.unwrap_or(1.0));
+
+    let rectangular = real().then(imag).map(|(re, im)| Complex64::new(re, im));
+    let imaginary = real()
+        .then_ignore(just('i'))
+        .map(|im| Complex64::new(0.0, im));
+
+    rectangular.or(imaginary)
+}
+
+fn unicode_escape() -> impl Parser<char, char, Error = Simple<char>> {
+    just('u').ignore_then(
+        filter(char::is_ascii_hexdigit)
+            .repeated()
+            .at_least(1)
+            .at_most(6)
+            .delimited_by(just('{'), just('}'))
+            .collect::<String>()
+            .validate(|digits, span, emit| {
+                char::from_u32(u32::from_str_radix(&digits, 16).unwrap()).unwrap_or_else(|| {
+                    emit(Simple::custom(
+                        span,
+                        format!("invalid Unicode character: \\u{digits}"),
+                    ));
+                    '\u{FFFD}' // unicode replacement character
+                })
+            }),
+    )
+}
+
 pub fn string() -> impl Parser<char, String, Error = Simple<char>> {
     let escape = just('\\').ignore_then(
         just('\\')
@@ -30,27 +128,50 @@ pub fn string() -> impl Parser<char, String, Error = Simple<char>> {
             .or(just('n').to('\n'))
             .or(just('r').to('\r'))
             .or(just('t').to('\t'))
-            .or(just('u').ignore_then(
-                filter(char::is_ascii_hexdigit)
-                    .repeated()
-                    .at_least(1)
-                    .at_most(6)
-                    .delimited_by(just('{'), just('}'))
-                    .collect::<String>()
-                    .validate(|digits, span, emit| {
-                        char::from_u32(u32::from_str_radix(&digits, 16).unwrap()).unwrap_or_else(
-                            || {
-                                emit(Simple::custom(
-                                    span,
-                                    format!("invalid Unicode character: \\u{digits}"),
-                                ));
-                                '\u{FFFD}' // unicode replacement character
-                            },
-                        )
-                    }),
-            )),
+            .or(unicode_escape()),
     );
 
     let string_body = filter(|&c| c != '\\' && c != '"').or(escape).repeated();
     string_body.delimited_by(just('"'), just('"')).collect()
 }
+
+pub fn character() -> impl Parser<char, char, Error = Simple<char>> {
+    let named = choice((
+        just("space").to(' '),
+        just("newline").to('\n'),
+        just("tab").to('\t'),
+        just("return").to('\r'),
+        just("nul").to('\0'),
+    ));
+
+    just("#\\").ignore_then(named.or(unicode_escape()).or(any()))
+}
+
+pub fn byte_string() -> impl Parser<char, Vec<u8>, Error = Simple<char>> {
+    let hex_escape = just('x').ignore_then(
+        filter(char::is_ascii_hexdigit)
+            .repeated()
+            .exactly(2)
+            .collect::<String>()
+            .map(|digits| u8::from_str_radix(&digits, 16).unwrap()),
+    );
+
+    let escape = just('\\').ignore_then(
+        just('\\')
+            .to(b'\\')
+            .or(just('"').to(b'"'))
+            .or(just('0').to(b'\0'))
+            .or(just('n').to(b'\n'))
+            .or(just('r').to(b'\r'))
+            .or(just('t').to(b'\t'))
+            .or(hex_escape),
+    );
+
+    let byte = escape
+        .map(|byte| vec![byte])
+        .or(filter(|&c| c != '\\' && c != '"').map(|c: char| c.to_string().into_bytes()));
+
+    byte.repeated()
+        .flatten()
+        .delimited_by(just("#b\""), just('"'))
+}
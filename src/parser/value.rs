@@ -1,8 +1,13 @@
+use std::{ops::Range, rc::Rc};
+
+use dumpster::unsync::Gc;
+
 use chumsky::prelude::*;
 
 use crate::{
-    Value,
-    parser::primitive::{float, string},
+    Symbol, Value,
+    parser::primitive::{byte_string, character, complex, float, integer, rational, string},
+    value::Compound,
 };
 
 static NON_SYMBOL_CHARS: &str = "()[]{}\"'`,@.";
@@ -21,11 +26,93 @@ fn raw_symbol() -> impl Parser<char, String, Error = Simple<char>> {
     symbol_head.chain(symbol_tail).collect()
 }
 
-fn raw_expr() -> impl Parser<char, Value, Error = Simple<char>> {
+fn compound_type() -> impl Parser<char, Symbol, Error = Simple<char>> {
+    just('#')
+        .ignore_then(filter(|&c| c != '\\').rewind())
+        .ignore_then(raw_symbol())
+        .map(Symbol::new)
+}
+
+fn list_with_tail(
+    (values, tail): (Vec<Value>, Option<Value>),
+    span: Range<usize>,
+    emit: &mut dyn FnMut(Simple<char>),
+) -> Value {
+    match tail {
+        Some(_) if values.is_empty() => {
+            emit(Simple::custom(span, "dotted list must have a head"));
+            Value::nil()
+        }
+        Some(tail) => Value::list_with_tail(values, tail),
+        None => Value::list(values),
+    }
+}
+
+fn compound_literal(
+    (type_, values): (Symbol, Vec<Value>),
+    span: Range<usize>,
+    emit: &mut dyn FnMut(Simple<char>),
+) -> Value {
+    if let Err(err) = Compound::check_arity(type_, values.len()) {
+        emit(Simple::custom(span, err.to_string()));
+    }
+    Value::compound(type_, values)
+}
+
+fn map_literal(
+    values: Vec<Value>,
+    span: Range<usize>,
+    emit: &mut dyn FnMut(Simple<char>),
+) -> Value {
+    if !values.len().is_multiple_of(2) {
+        emit(Simple::custom(
+            span,
+            "map literal must have an even number of forms",
+        ));
+    }
+
+    let mut values = values.into_iter();
+    let entries: Vec<_> = std::iter::from_fn(|| Some((values.next()?, values.next()?))).collect();
+    Value::map(entries)
+}
+
+// Char offsets at which each line of a source starts.
+fn line_starts(s: &str) -> Rc<[usize]> {
+    std::iter::once(0)
+        .chain(
+            s.chars()
+                .enumerate()
+                .filter(|&(_, c)| c == '\n')
+                .map(|(i, _)| i + 1),
+        )
+        .collect()
+}
+
+fn at_line(value: Value, span: &Range<usize>, line_starts: Option<&[usize]>) -> Value {
+    match (&value, line_starts) {
+        (Value::Compound(cons), Some(line_starts)) => {
+            let line = line_starts.partition_point(|&start| start <= span.start);
+            let line = u32::try_from(line).unwrap_or(u32::MAX);
+            Value::Compound(Gc::new(cons.at_line(line)))
+        }
+        _ => value,
+    }
+}
+
+fn raw_expr(line_starts: Option<Rc<[usize]>>) -> impl Parser<char, Value, Error = Simple<char>> {
     recursive(|expr| {
         let symbol = raw_symbol().map(Value::symbol).labelled("symbol");
-        let number = float().map(Value::from).labelled("number");
+        let complex = complex().map(Value::from).labelled("complex");
+        let float = float().map(Value::from).labelled("float");
+        let rational = rational().map(Value::from).labelled("rational");
+        let integer = integer().map(Value::from).labelled("integer");
         let string = string().map(Value::from).labelled("string");
+        let character = character().map(Value::from).labelled("character");
+        let byte_string = byte_string().map(Value::from).labelled("byte_string");
+        let keyword = just(':')
+            .ignore_then(raw_symbol())
+            .map(Value::keyword)
+            .labelled("keyword");
 
         let quote = just('\'')
             .ignore_then(expr.clone())
@@ -47,29 +134,68 @@ fn raw_expr() -> impl Parser<char, Value, Error = Simple<char>> {
             .map(Value::unquote_splicing)
             .labelled("unquote_splicing");
 
+        let prefix_compound = compound_type()
+            .then(expr.clone().repeated().delimited_by(just('('), just(')')))
+            .validate(compound_literal)
+            .labelled("compound");
+
+        let list_compound = compound_type()
+            .then_ignore(filter(|&c: &char| c.is_whitespace() || c == ')').rewind())
+            .padded()
+            .then(expr.clone().repeated())
+            .delimited_by(just('('), just(')'))
+            .validate(compound_literal)
+            .labelled("compound");
+
         let list = expr
             .clone()
             .repeated()
+            .then(just('.').padded().ignore_then(expr.clone()).or_not())
             .delimited_by(just('('), just(')'))
-            .map(Value::list)
+            .validate(list_with_tail)
+            .map_with_span(move |value, span| at_line(value, &span, line_starts.as_deref()))
             .labelled("list");
 
-        let square_list = expr
+        let vector = expr
             .clone()
             .repeated()
             .delimited_by(just('['), just(']'))
-            .map(Value::list)
-            .labelled("square_list");
+            .map(Value::vector)
+            .labelled("vector");
+
+        let map = expr
+            .clone()
+            .repeated()
+            .delimited_by(just('{'), just('}'))
+            .validate(map_literal)
+            .labelled("map");
+
+        let set = expr
+            .clone()
+            .repeated()
+            .delimited_by(just("#{"), just('}'))
+            .map(Value::set)
+            .labelled("set");
 
         choice((
-            number,
+            complex,
+            float,
+            rational,
+            integer,
             string,
+            character,
+            byte_string,
+            keyword,
             quote,
             quasiquote,
             unquote,
             unquote_splicing,
+            prefix_compound,
+            list_compound,
             list,
-            square_list,
+            vector,
+            map,
+            set,
             symbol,
         ))
         .padded()
@@ -77,10 +203,15 @@ fn raw_expr() -> impl Parser<char, Value, Error = Simple<char>> {
 }
 
 pub fn value() -> impl Parser<char, Value, Error = Simple<char>> {
-    raw_expr().then_ignore(end())
+    raw_expr(None).then_ignore(end())
 }
 
 #[allow(dead_code)]
 pub fn values() -> impl Parser<char, Vec<Value>, Error = Simple<char>> {
-    raw_expr().repeated().then_ignore(end())
+    raw_expr(None).repeated().then_ignore(end())
+}
+
+// Every form in `s`, with lists recording the line they start on.
+pub fn source(s: &str) -> impl Parser<char, Vec<Value>, Error = Simple<char>> {
+    raw_expr(Some(line_starts(s))).repeated().then_ignore(end())
 }
@@ -0,0 +1,349 @@
+mod native;
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+
+use crate::{Pattern, Result, Symbol, Value};
+
+pub(crate) use native::{define_all, to_rules};
+
+pub const DEFAULT_MAX_STEPS: usize = 10_000;
+
+// A rule `pattern -> replacement`. Symbols in the pattern are variables, and a variable that
+// occurs more than once must match equal values. The replacement is a template in which bound
+// variables are substituted; it is not evaluated.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Rule {
+    pub name: Option<Symbol>,
+    pub pattern: Pattern,
+    pub replacement: Value,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Strategy {
+    // Rewrite children to normal form first, then the term itself, until nothing applies.
+    Innermost,
+    // Repeatedly rewrite the leftmost outermost redex until nothing applies.
+    Outermost,
+    // A single pass trying each subterm once, children before parents.
+    BottomUp,
+    // A single pass trying each subterm once, parents before children.
+    TopDown,
+    // Repeat bottom-up passes until the term stops changing.
+    Fixpoint,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Step {
+    pub rule: usize,
+    pub before: Value,
+    pub after: Value,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Rewrite {
+    pub value: Value,
+    pub trace: Vec<Step>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Rewriter {
+    rules: Vec<Rule>,
+    strategy: Strategy,
+    max_steps: usize,
+}
+
+struct Run<'a> {
+    rules: &'a [Rule],
+    max_steps: usize,
+    trace: Vec<Step>,
+}
+
+impl Rule {
+    pub fn new(pattern: &Value, replacement: Value) -> Result<Self> {
+        Ok(Rule {
+            name: None,
+            pattern: pattern.try_into()?,
+            replacement,
+        })
+    }
+
+    #[must_use]
+    pub fn named<S: Into<Symbol>>(self, name: S) -> Self {
+        Rule {
+            name: Some(name.into()),
+            ..self
+        }
+    }
+
+    // Rewrites `value` itself, without looking at its subterms.
+    #[must_use]
+    pub fn apply(&self, value: &Value) -> Option<Value> {
+        let mut values = Vec::new();
+        if !self.pattern.matches(value, &mut values) {
+            return None;
+        }
+
+        let mut bindings = HashMap::new();
+        for (var, value) in self.pattern.vars().into_iter().zip(values) {
+            if let Some(bound) = bindings.insert(var, value.clone())
+                && bound != value
+            {
+                return None;
+            }
+        }
+
+        Some(substitute(&self.replacement, &bindings))
+    }
+}
+
+fn substitute(template: &Value, bindings: &HashMap<Symbol, Value>) -> Value {
+    match template {
+        Value::Symbol(sym) => bindings
+            .get(sym)
+            .cloned()
+            .unwrap_or_else(|| template.clone()),
+        Value::Compound(quote) if quote.is_quote() => quote.values[0].clone(),
+        Value::Compound(compound) => Value::compound(
+            compound.type_,
+            compound
+                .values
+                .iter()
+                .map(|value| substitute(value, bindings))
+                .collect(),
+        ),
+        Value::Vector(values) => values
+            .iter()
+            .map(|value| substitute(value, bindings))
+            .collect::<im::Vector<_>>()
+            .into(),
+        _ => template.clone(),
+    }
+}
+
+fn children(value: &Value) -> &[Value] {
+    match value {
+        Value::Compound(compound) => &compound.values,
+        _ => &[],
+    }
+}
+
+// Rebuilds a compound with new children, keeping the original when nothing changed.
+fn with_children(value: &Value, children: Vec<Value>) -> Value {
+    match value {
+        Value::Compound(compound) if compound.values != children => {
+            Value::compound(compound.type_, children)
+        }
+        _ => value.clone(),
+    }
+}
+
+impl Run<'_> {
+    fn at_root(&mut self, value: &Value) -> Result<Option<Value>> {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if let Some(after) = rule.apply(value) {
+                if self.trace.len() >= self.max_steps {
+                    return Err(anyhow!("rewrite step limit of {} exceeded", self.max_steps));
+                }
+
+                self.trace.push(Step {
+                    rule: i,
+                    before: value.clone(),
+                    after: after.clone(),
+                });
+                return Ok(Some(after));
+            }
+        }
+        Ok(None)
+    }
+
+    fn map_children<F>(&mut self, value: &Value, mut f: F) -> Result<Value>
+    where
+        F: FnMut(&mut Self, &Value) -> Result<Value>,
+    {
+        let children = children(value)
+            .iter()
+            .map(|child| f(self, child))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(with_children(value, children))
+    }
+
+    fn innermost(&mut self, value: &Value) -> Result<Value> {
+        let mut value = self.map_children(value, Self::innermost)?;
+        while let Some(next) = self.at_root(&value)? {
+            value = self.map_children(&next, Self::innermost)?;
+        }
+        Ok(value)
+    }
+
+    // Performs one rewrite at the leftmost outermost redex, if there is one.
+    fn outermost_step(&mut self, value: &Value) -> Result<Option<Value>> {
+        if let Some(next) = self.at_root(value)? {
+            return Ok(Some(next));
+        }
+
+        let children = children(value);
+        for (i, child) in children.iter().enumerate() {
+            if let Some(next) = self.outermost_step(child)? {
+                let mut children = children.to_vec();
+                children[i] = next;
+                return Ok(Some(with_children(value, children)));
+            }
+        }
+        Ok(None)
+    }
+
+    fn bottom_up(&mut self, value: &Value) -> Result<Value> {
+        let value = self.map_children(value, Self::bottom_up)?;
+        Ok(self.at_root(&value)?.unwrap_or(value))
+    }
+
+    fn top_down(&mut self, value: &Value) -> Result<Value> {
+        let value = self.at_root(value)?.unwrap_or_else(|| value.clone());
+        self.map_children(&value, Self::top_down)
+    }
+
+    fn run(&mut self, strategy: Strategy, value: &Value) -> Result<Value> {
+        match strategy {
+            Strategy::Innermost => self.innermost(value),
+            Strategy::Outermost => {
+                let mut value = value.clone();
+                while let Some(next) = self.outermost_step(&value)? {
+                    value = next;
+                }
+                Ok(value)
+            }
+            Strategy::BottomUp => self.bottom_up(value),
+            Strategy::TopDown => self.top_down(value),
+            Strategy::Fixpoint => {
+                let mut value = value.clone();
+                loop {
+                    let steps = self.trace.len();
+                    value = self.bottom_up(&value)?;
+                    if self.trace.len() == steps {
+                        return Ok(value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Rewriter {
+    #[must_use]
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Rewriter {
+            rules,
+            strategy: Strategy::Innermost,
+            max_steps: DEFAULT_MAX_STEPS,
+        }
+    }
+
+    #[must_use]
+    pub fn strategy(self, strategy: Strategy) -> Self {
+        Rewriter { strategy, ..self }
+    }
+
+    #[must_use]
+    pub fn max_steps(self, max_steps: usize) -> Self {
+        Rewriter { max_steps, ..self }
+    }
+
+    #[must_use]
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn rewrite(&self, value: &Value) -> Result<Rewrite> {
+        let mut run = Run {
+            rules: &self.rules,
+            max_steps: self.max_steps,
+            trace: Vec::new(),
+        };
+        let value = run.run(self.strategy, value)?;
+        Ok(Rewrite {
+            value,
+            trace: run.trace,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Module, VM, Value, parser};
+
+    use super::{Rewriter, Rule, Strategy};
+
+    fn parse(s: &str) -> Value {
+        parser::parse(s, parser::value()).unwrap()
+    }
+
+    fn rule(pattern: &str, replacement: &str) -> Rule {
+        Rule::new(&parse(pattern), parse(replacement)).unwrap()
+    }
+
+    #[test]
+    fn strategies() {
+        let rules = vec![
+            rule("(#add 0 x)", "x"),
+            rule("(#mul 1 x)", "x"),
+            rule("(#sub x x)", "0"),
+        ];
+        let term = parse("(#mul 1 (#add 0 (#sub (#f y) (#f y))))");
+
+        for strategy in [Strategy::Innermost, Strategy::Outermost, Strategy::Fixpoint] {
+            let rewrite = Rewriter::new(rules.clone())
+                .strategy(strategy)
+                .rewrite(&term)
+                .unwrap();
+            assert_eq!(Value::from(0), rewrite.value);
+            assert_eq!(3, rewrite.trace.len());
+        }
+
+        let rewrite = Rewriter::new(rules.clone())
+            .strategy(Strategy::TopDown)
+            .rewrite(&term)
+            .unwrap();
+        assert_eq!(parse("(#add 0 0)"), rewrite.value);
+        assert_eq!(
+            vec![1, 2],
+            rewrite.trace.iter().map(|s| s.rule).collect::<Vec<_>>()
+        );
+
+        let rewrite = Rewriter::new(rules)
+            .strategy(Strategy::BottomUp)
+            .rewrite(&term)
+            .unwrap();
+        assert_eq!(
+            vec![2, 0, 1],
+            rewrite.trace.iter().map(|s| s.rule).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn step_limit() {
+        let rewriter = Rewriter::new(vec![rule("(#f x)", "(#f (#g x))")]).max_steps(10);
+        let err = rewriter.rewrite(&parse("(#f a)")).unwrap_err();
+        assert_eq!("rewrite step limit of 10 exceeded", err.to_string());
+    }
+
+    #[test]
+    fn natives() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+
+        assert_eq!(
+            "[y [(#step :add-zero (#add 0 y) y)]]",
+            module
+                .eval_str("(rewrite (rules '[:add-zero (#add 0 x) -> x]) '#add(0 y))")
+                .unwrap()
+                .to_string()
+        );
+
+        let err = module.eval_str("(rules 1)").unwrap_err();
+        assert_eq!("expected list or vector, got 1", err.to_string());
+        let err = module.eval_str("(rewrite 1 [])").unwrap_err();
+        assert_eq!("expected list or vector, got 1", err.to_string());
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::anyhow;
+
+use crate::{Module, Result, ResultIterator, Symbol, Value, try_as_array};
+
+use super::{DEFAULT_MAX_STEPS, Rewriter, Rule, Strategy};
+
+// Rules are represented in jymbol as `(#rule name pattern replacement)`, with a nil name for
+// anonymous rules.
+fn rule_type() -> Symbol {
+    Symbol::new("rule")
+}
+
+fn rule_value(name: Value, pattern: &Value, replacement: &Value) -> Result<Value> {
+    Rule::new(pattern, replacement.clone())?;
+    Ok(Value::compound(
+        rule_type(),
+        vec![name, pattern.clone(), replacement.clone()],
+    ))
+}
+
+pub(crate) fn to_rules(value: &Value) -> Result<Vec<Rule>> {
+    value.as_items()?.iter().map(to_rule).try_collect()
+}
+
+fn to_rule(value: &Value) -> Result<Rule> {
+    let compound = value.as_compound()?;
+    let [name, pattern, replacement] = compound.as_checked_array(rule_type())?;
+    let rule = Rule::new(pattern, replacement.clone())?;
+    Ok(match name {
+        Value::Keyword(name) => rule.named(*name),
+        _ => rule,
+    })
+}
+
+fn rule(values: &[Value]) -> Result<Value> {
+    match values {
+        [pattern, replacement] => rule_value(Value::nil(), pattern, replacement),
+        [name, pattern, replacement] => {
+            rule_value(Value::Keyword(name.as_keyword()?), pattern, replacement)
+        }
+        _ => Err(anyhow!("expected 2 or 3 arguments, got {}", values.len())),
+    }
+}
+
+// `(rules '[(#add 0 x) -> x  :mul-one (#mul 1 x) -> x])`
+fn rules(values: &[Value]) -> Result<Value> {
+    let [spec] = try_as_array(values)?;
+    let spec = spec.as_items()?;
+    let arrow = Value::symbol("->");
+
+    let mut rules = Vec::new();
+    let mut rest = spec.as_slice();
+    while !rest.is_empty() {
+        let rule;
+        (rule, rest) = match rest {
+            [name @ Value::Keyword(_), pattern, a, replacement, rest @ ..] if *a == arrow => {
+                (rule_value(name.clone(), pattern, replacement)?, rest)
+            }
+            [pattern, a, replacement, rest @ ..] if *a == arrow => {
+                (rule_value(Value::nil(), pattern, replacement)?, rest)
+            }
+            _ => return Err(anyhow!("expected `pattern -> replacement`")),
+        };
+        rules.push(rule);
+    }
+
+    Ok(Value::vector(rules))
+}
+
+fn strategy(value: &Value) -> Result<Strategy> {
+    match value.as_keyword()?.as_str() {
+        "innermost" => Ok(Strategy::Innermost),
+        "outermost" => Ok(Strategy::Outermost),
+        "bottom-up" => Ok(Strategy::BottomUp),
+        "top-down" => Ok(Strategy::TopDown),
+        "fixpoint" => Ok(Strategy::Fixpoint),
+        _ => Err(anyhow!("unknown rewrite strategy {value}")),
+    }
+}
+
+// `(rewrite rules term [strategy [max-steps]])` returns `[result trace]`, where each trace
+// entry is `(#step rule before after)` and `rule` is the rule's name or index.
+fn rewrite(values: &[Value]) -> Result<Value> {
+    let (rules, term, strategy_value, max_steps) = match values {
+        [rules, term] => (rules, term, None, None),
+        [rules, term, strategy] => (rules, term, Some(strategy), None),
+        [rules, term, strategy, max_steps] => (rules, term, Some(strategy), Some(max_steps)),
+        _ => return Err(anyhow!("expected 2 to 4 arguments, got {}", values.len())),
+    };
+
+    let rules = to_rules(rules)?;
+    let max_steps = match max_steps {
+        Some(max_steps) => usize::try_from(max_steps.as_integer()?)?,
+        None => DEFAULT_MAX_STEPS,
+    };
+    let mut rewriter = Rewriter::new(rules).max_steps(max_steps);
+    if let Some(strategy_value) = strategy_value {
+        rewriter = rewriter.strategy(strategy(strategy_value)?);
+    }
+
+    let rewrite = rewriter.rewrite(term)?;
+    let trace: Vec<Value> = rewrite
+        .trace
+        .into_iter()
+        .map(|step| {
+            let rule = match rewriter.rules()[step.rule].name {
+                Some(name) => Value::Keyword(name),
+                None => Value::from(i64::try_from(step.rule).unwrap_or(i64::MAX)),
+            };
+            Value::compound(Symbol::new("step"), vec![rule, step.before, step.after])
+        })
+        .collect();
+
+    Ok(Value::vector([rewrite.value, Value::vector(trace)]))
+}
+
+pub fn define_all(module: &mut Module) {
+    module.set_native("rule", rule, 2..);
+    module.set_native("rules", rules, 1);
+    module.set_native("rewrite", rewrite, 2..);
+}
@@ -3,7 +3,7 @@ use std::{collections::HashMap, sync::LazyLock};
 use im::OrdSet;
 
 use crate::{
-    Expr, Result, Symbol, Value,
+    Expr, Result, Symbol, Value, logic,
     op::{Binary, Unary},
     symbol,
 };
@@ -17,9 +17,17 @@ pub static FUNCTIONS: LazyLock<HashMap<Symbol, SpecialFn>> = LazyLock::new(|| {
     functions.insert("do".into(), Expr::try_from_do);
     functions.insert("fn".into(), Expr::try_from_fn);
     functions.insert("let".into(), Expr::try_from_let);
+    functions.insert("letrec".into(), Expr::try_from_letrec);
+    functions.insert("letfn".into(), Expr::try_from_letfn);
     functions.insert("if".into(), Expr::try_from_if);
+    functions.insert("match".into(), Expr::try_from_match);
     functions.insert("loop".into(), Expr::try_from_loop);
     functions.insert("recur".into(), Expr::try_from_recur);
+    functions.insert("set!".into(), Expr::try_from_set);
+    functions.insert("fresh".into(), logic::try_from_fresh);
+    functions.insert("conde".into(), logic::try_from_conde);
+    functions.insert("run".into(), logic::try_from_run);
+    functions.insert("run*".into(), logic::try_from_run_all);
 
     functions.insert("$abs".into(), |values| {
         Expr::try_from_unop(Unary::Abs, values)
@@ -117,12 +117,38 @@ static_symbols! {
     FALSE = "false",
     BOOLEAN = "boolean",
     SYMBOL = "symbol",
-    NUMBER = "number",
+    INTEGER = "integer",
+    RATIONAL = "rational",
+    FLOAT = "float",
+    COMPLEX = "complex",
     STRING = "string",
+    CHAR = "char",
+    KEYWORD = "keyword",
+    BYTES = "bytes",
+    VECTOR = "vector",
+    MAP = "map",
+    SET = "set",
+    BOX = "box",
+    LVAR = "lvar",
     QUOTE = "quote",
     QUASIQUOTE = "quasiquote",
     UNQUOTE = "unquote",
     UNQUOTE_SPLICING = "unquote-splicing",
     FN = "fn",
     NATIVE_FN = "native-fn",
+    EVAL = "eval",
+    WILDCARD = "_",
+    AMPERSAND = "&",
+    OR = "or",
+    WHEN = "when",
+    ADD = "add",
+    SUB = "sub",
+    NEG = "neg",
+    MUL = "mul",
+    DIV = "div",
+    POW = "pow",
+    EXP = "exp",
+    LOG = "log",
+    SIN = "sin",
+    COS = "cos",
 }
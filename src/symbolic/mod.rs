@@ -0,0 +1,405 @@
+mod native;
+
+use anyhow::anyhow;
+use im::OrdMap;
+
+use crate::{Pretty, Result, ResultIterator, Symbol, Value, op::Binary, symbol};
+
+pub(crate) use native::define_all;
+
+// Expressions are numbers, symbols and compounds such as `(#add a b ...)`, `(#mul a b ...)`,
+// `(#pow base exponent)` and applications of `#exp`, `#log`, `#sin` and `#cos`. `#sub`,
+// `#neg` and `#div` are accepted as input and rewritten in terms of `#add`, `#mul` and
+// `#pow` by `simplify`.
+pub type Bindings = im::HashMap<Value, Value>;
+
+fn is_number(value: &Value, num: i64) -> bool {
+    value.numeric_cmp(&num.into()) == Some(std::cmp::Ordering::Equal)
+}
+
+fn args(value: &Value, type_: Symbol) -> Option<&[Value]> {
+    match value {
+        Value::Compound(compound) if compound.type_ == type_ => Some(&compound.values),
+        _ => None,
+    }
+}
+
+fn add(values: Vec<Value>) -> Value {
+    Value::compound(*symbol::ADD, values)
+}
+
+fn mul(values: Vec<Value>) -> Value {
+    Value::compound(*symbol::MUL, values)
+}
+
+fn pow(base: Value, exponent: Value) -> Value {
+    Value::compound(*symbol::POW, vec![base, exponent])
+}
+
+// Splits a term into its numeric coefficient and the rest, so `(#mul 3 x y)` becomes
+// `3` and `(#mul x y)`.
+fn split_coefficient(term: Value) -> (Value, Value) {
+    if let Some([coefficient, rest @ ..]) = args(&term, *symbol::MUL)
+        && coefficient.is_number()
+    {
+        let rest = match rest {
+            [factor] => factor.clone(),
+            _ => mul(rest.to_vec()),
+        };
+        (coefficient.clone(), rest)
+    } else {
+        (1.into(), term)
+    }
+}
+
+fn simplify_add(terms: Vec<Value>) -> Result<Value> {
+    let mut constant = Value::from(0);
+    let mut coefficients: OrdMap<Value, Value> = OrdMap::new();
+    let mut pending = terms;
+    while let Some(term) = pending.pop() {
+        if let Some(terms) = args(&term, *symbol::ADD) {
+            pending.extend(terms.iter().cloned());
+        } else if term.is_number() {
+            constant = Binary::Add.apply(&constant, &term)?;
+        } else {
+            let (coefficient, rest) = split_coefficient(term);
+            let sum = match coefficients.get(&rest) {
+                Some(sum) => Binary::Add.apply(sum, &coefficient)?,
+                None => coefficient,
+            };
+            coefficients.insert(rest, sum);
+        }
+    }
+
+    let mut terms = Vec::new();
+    if !is_number(&constant, 0) {
+        terms.push(constant.clone());
+    }
+    for (rest, coefficient) in coefficients {
+        if !is_number(&coefficient, 0) {
+            terms.push(simplify_mul(vec![coefficient, rest])?);
+        }
+    }
+
+    Ok(match terms.len() {
+        0 => constant,
+        1 => terms.pop().unwrap_or(constant),
+        _ => add(terms),
+    })
+}
+
+fn simplify_mul(factors: Vec<Value>) -> Result<Value> {
+    let mut coefficient = Value::from(1);
+    let mut exponents: OrdMap<Value, Value> = OrdMap::new();
+    let mut pending = factors;
+    while let Some(factor) = pending.pop() {
+        if let Some(factors) = args(&factor, *symbol::MUL) {
+            pending.extend(factors.iter().cloned());
+        } else if factor.is_number() {
+            coefficient = Binary::Mul.apply(&coefficient, &factor)?;
+        } else {
+            let (base, exponent) = match args(&factor, *symbol::POW) {
+                Some([base, exponent]) => (base.clone(), exponent.clone()),
+                _ => (factor, 1.into()),
+            };
+            let sum = match exponents.get(&base) {
+                Some(sum) => simplify_add(vec![sum.clone(), exponent])?,
+                None => exponent,
+            };
+            exponents.insert(base, sum);
+        }
+    }
+
+    let mut factors = Vec::new();
+    for (base, exponent) in exponents {
+        match simplify_pow(base, exponent)? {
+            factor if factor.is_number() => {
+                coefficient = Binary::Mul.apply(&coefficient, &factor)?;
+            }
+            factor => factors.push(factor),
+        }
+    }
+
+    if is_number(&coefficient, 0) || factors.is_empty() {
+        return Ok(coefficient);
+    }
+    if !is_number(&coefficient, 1) {
+        factors.insert(0, coefficient);
+    }
+    Ok(match factors.len() {
+        1 => factors.remove(0),
+        _ => mul(factors),
+    })
+}
+
+fn simplify_pow(base: Value, exponent: Value) -> Result<Value> {
+    let integer_exponent = exponent.to_big_integer().is_some();
+    if is_number(&exponent, 0) {
+        return Ok(1.into());
+    }
+    if is_number(&exponent, 1) || is_number(&base, 1) {
+        return Ok(base);
+    }
+    if base.is_number()
+        && exponent.is_number()
+        && (integer_exponent || !base.is_exact() || !exponent.is_exact())
+    {
+        return Binary::Pow.apply(&base, &exponent);
+    }
+    if integer_exponent {
+        // (x^a)^n = x^(a*n) and (x*y)^n = x^n * y^n only hold for integer n.
+        if let Some([inner_base, inner_exponent]) = args(&base, *symbol::POW) {
+            let exponent = simplify_mul(vec![inner_exponent.clone(), exponent])?;
+            return simplify_pow(inner_base.clone(), exponent);
+        }
+        if let Some(factors) = args(&base, *symbol::MUL) {
+            let factors = factors
+                .iter()
+                .map(|factor| simplify_pow(factor.clone(), exponent.clone()))
+                .collect::<Result<Vec<_>>>()?;
+            return simplify_mul(factors);
+        }
+    }
+    Ok(pow(base, exponent))
+}
+
+// Canonicalizes an expression: sums and products are flattened, constants are folded, like
+// terms and factors are combined, and operands are sorted.
+pub fn simplify(value: &Value) -> Result<Value> {
+    let Value::Compound(compound) = value else {
+        return Ok(value.clone());
+    };
+    if compound.is_quote() {
+        return Ok(value.clone());
+    }
+
+    let values: Vec<Value> = compound.values.iter().map(simplify).try_collect()?;
+    let type_ = compound.type_;
+    match values.as_slice() {
+        _ if type_ == *symbol::ADD => simplify_add(values),
+        _ if type_ == *symbol::MUL => simplify_mul(values),
+        [base, exponent] if type_ == *symbol::POW => simplify_pow(base.clone(), exponent.clone()),
+        [a, b] if type_ == *symbol::SUB => {
+            let b = simplify_mul(vec![(-1).into(), b.clone()])?;
+            simplify_add(vec![a.clone(), b])
+        }
+        [a] if type_ == *symbol::NEG => simplify_mul(vec![(-1).into(), a.clone()]),
+        [a, b] if type_ == *symbol::DIV => {
+            let b = simplify_pow(b.clone(), (-1).into())?;
+            simplify_mul(vec![a.clone(), b])
+        }
+        [a] if type_ == *symbol::EXP && is_number(a, 0) => Ok(1.into()),
+        [a] if type_ == *symbol::LOG && is_number(a, 1) => Ok(0.into()),
+        _ => Ok(Value::compound(type_, values)),
+    }
+}
+
+fn contains(value: &Value, var: Symbol) -> bool {
+    match value {
+        &Value::Symbol(sym) => sym == var,
+        Value::Compound(compound) => compound.values.iter().any(|v| contains(v, var)),
+        _ => false,
+    }
+}
+
+fn unary(type_: Symbol, value: &Value) -> Value {
+    Value::compound(type_, vec![value.clone()])
+}
+
+// Differentiates an expression already in canonical form.
+fn derivative(value: &Value, var: Symbol) -> Result<Value> {
+    if !contains(value, var) {
+        return Ok(0.into());
+    }
+    let Value::Compound(compound) = value else {
+        return Ok(1.into());
+    };
+
+    let type_ = compound.type_;
+    let values = compound.values.as_slice();
+    Ok(match values {
+        _ if type_ == *symbol::ADD => add(values
+            .iter()
+            .map(|value| derivative(value, var))
+            .try_collect()?),
+        _ if type_ == *symbol::MUL => {
+            // Product rule, one term per factor.
+            let mut terms = Vec::new();
+            for i in 0..values.len() {
+                let mut factors = values.to_vec();
+                factors[i] = derivative(&values[i], var)?;
+                terms.push(mul(factors));
+            }
+            add(terms)
+        }
+        [base, exponent] if type_ == *symbol::POW && !contains(exponent, var) => mul(vec![
+            exponent.clone(),
+            pow(base.clone(), add(vec![exponent.clone(), (-1).into()])),
+            derivative(base, var)?,
+        ]),
+        [base, exponent] if type_ == *symbol::POW => mul(vec![
+            value.clone(),
+            add(vec![
+                mul(vec![derivative(exponent, var)?, unary(*symbol::LOG, base)]),
+                mul(vec![
+                    exponent.clone(),
+                    derivative(base, var)?,
+                    pow(base.clone(), (-1).into()),
+                ]),
+            ]),
+        ]),
+        [arg] if type_ == *symbol::EXP => mul(vec![value.clone(), derivative(arg, var)?]),
+        [arg] if type_ == *symbol::LOG => {
+            mul(vec![derivative(arg, var)?, pow(arg.clone(), (-1).into())])
+        }
+        [arg] if type_ == *symbol::SIN => {
+            mul(vec![unary(*symbol::COS, arg), derivative(arg, var)?])
+        }
+        [arg] if type_ == *symbol::COS => mul(vec![
+            (-1).into(),
+            unary(*symbol::SIN, arg),
+            derivative(arg, var)?,
+        ]),
+        _ => {
+            return Err(anyhow!(
+                "can't differentiate {}",
+                Pretty::brief().render(value)
+            ));
+        }
+    })
+}
+
+// The simplified derivative of `value` with respect to `var`.
+pub fn diff(value: &Value, var: Symbol) -> Result<Value> {
+    simplify(&derivative(&simplify(value)?, var)?)
+}
+
+// Replaces symbols bound in `bindings`, without simplifying the result.
+#[must_use]
+pub fn subst(value: &Value, bindings: &Bindings) -> Value {
+    match value {
+        Value::Symbol(_) => bindings
+            .get(value)
+            .cloned()
+            .unwrap_or_else(|| value.clone()),
+        Value::Compound(compound) if !compound.is_quote() => Value::compound(
+            compound.type_,
+            compound.values.iter().map(|v| subst(v, bindings)).collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+fn float_fn(type_: Symbol) -> Option<fn(f64) -> f64> {
+    match type_ {
+        _ if type_ == *symbol::EXP => Some(f64::exp),
+        _ if type_ == *symbol::LOG => Some(f64::ln),
+        _ if type_ == *symbol::SIN => Some(f64::sin),
+        _ if type_ == *symbol::COS => Some(f64::cos),
+        _ => None,
+    }
+}
+
+// Evaluates an expression numerically, looking up symbols in `bindings`.
+pub fn evaluate(value: &Value, bindings: &Bindings) -> Result<Value> {
+    match value {
+        _ if value.is_number() => Ok(value.clone()),
+        Value::Symbol(_) => bindings
+            .get(value)
+            .cloned()
+            .ok_or_else(|| anyhow!("no value for {value} in expression")),
+        Value::Compound(compound) => {
+            let values: Vec<Value> = compound
+                .values
+                .iter()
+                .map(|v| evaluate(v, bindings))
+                .try_collect()?;
+            let type_ = compound.type_;
+            let fold = |op: Binary, init: i64| {
+                values
+                    .iter()
+                    .try_fold(Value::from(init), |acc, v| op.apply(&acc, v))
+            };
+            match values.as_slice() {
+                _ if type_ == *symbol::ADD => fold(Binary::Add, 0),
+                _ if type_ == *symbol::MUL => fold(Binary::Mul, 1),
+                [a, b] if type_ == *symbol::POW => Binary::Pow.apply(a, b),
+                [a, b] if type_ == *symbol::SUB => Binary::Sub.apply(a, b),
+                [a, b] if type_ == *symbol::DIV => Binary::Div.apply(a, b),
+                [a] if type_ == *symbol::NEG => Binary::Sub.apply(&0.into(), a),
+                [a] => match float_fn(type_) {
+                    Some(f) => Ok(f(a.as_number()?).into()),
+                    None => Err(anyhow!("can't evaluate {}", Pretty::brief().render(value))),
+                },
+                _ => Err(anyhow!("can't evaluate {}", Pretty::brief().render(value))),
+            }
+        }
+        _ => Err(anyhow!("can't evaluate {}", Pretty::brief().render(value))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Symbol, Value, parser};
+
+    use super::{Bindings, diff, evaluate, simplify, subst};
+
+    fn parse(s: &str) -> Value {
+        parser::parse(s, parser::value()).unwrap()
+    }
+
+    fn simplified(s: &str) -> String {
+        simplify(&parse(s)).unwrap().to_string()
+    }
+
+    #[test]
+    fn simplification() {
+        assert_eq!(
+            "(#add 3 (#mul 2 x) y)",
+            simplified("(#add x (#add 1 y) x 2)")
+        );
+        assert_eq!("(#pow x 3)", simplified("(#mul x (#pow x 2))"));
+        assert_eq!("(#mul 6 x y)", simplified("(#mul 2 y (#mul 3 x))"));
+        assert_eq!("0", simplified("(#sub (#mul x y) (#mul y x))"));
+        assert_eq!("1", simplified("(#div (#pow x 2) (#mul x x))"));
+        assert_eq!("(#pow 2 1/2)", simplified("(#pow 2 1/2)"));
+        assert_eq!(
+            simplified("(#add (#mul a b) c)"),
+            simplified("(#add c (#mul b a))")
+        );
+    }
+
+    #[test]
+    fn differentiation() {
+        let x = Symbol::new("x");
+        let d = |s: &str| diff(&parse(s), x).unwrap().to_string();
+
+        assert_eq!(
+            "(#add 2 (#mul 6 x))",
+            d("(#add (#mul 3 (#pow x 2)) (#mul 2 x) y)")
+        );
+        assert_eq!("(#mul -1 (#pow x -2))", d("(#div 1 x)"));
+        assert_eq!("(#mul 2 x (#exp (#pow x 2)))", d("(#exp (#pow x 2))"));
+        assert_eq!("(#mul -1 (#sin x))", d("(#cos x)"));
+        assert!(diff(&parse("(#f x)"), x).is_err());
+    }
+
+    #[test]
+    fn evaluation() {
+        let expr = parse("(#add (#pow x 2) (#mul 3 y))");
+        let bindings: Bindings = [(parse("x"), 2.into()), (parse("y"), parse("1/3"))]
+            .into_iter()
+            .collect();
+        assert_eq!(Value::from(5), evaluate(&expr, &bindings).unwrap());
+        assert_eq!(
+            parse("(#add (#pow 2 2) (#mul 3 1/3))"),
+            subst(&expr, &bindings)
+        );
+        assert_eq!(
+            "no value for y in expression",
+            evaluate(&expr, &Bindings::unit(parse("x"), 1.into()))
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
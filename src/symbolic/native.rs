@@ -0,0 +1,46 @@
+use anyhow::anyhow;
+
+use crate::{Module, Result, Value, try_as_array};
+
+use super::{Bindings, diff, evaluate, simplify, subst};
+
+fn simplify_(values: &[Value]) -> Result<Value> {
+    let [value] = try_as_array(values)?;
+    simplify(value)
+}
+
+fn diff_(values: &[Value]) -> Result<Value> {
+    let [value, var] = try_as_array(values)?;
+    diff(value, var.as_symbol()?)
+}
+
+// `(subst expr {x 1 y 2})` or `(subst expr 'x 1)`
+fn subst_(values: &[Value]) -> Result<Value> {
+    match values {
+        [value, map] => {
+            let bindings = map.as_map()?;
+            Ok(subst(value, &bindings))
+        }
+        [value, var, replacement] => {
+            var.as_symbol()?;
+            Ok(subst(
+                value,
+                &Bindings::unit(var.clone(), replacement.clone()),
+            ))
+        }
+        _ => Err(anyhow!("expected 2 or 3 arguments, got {}", values.len())),
+    }
+}
+
+fn evaluate_(values: &[Value]) -> Result<Value> {
+    let [value, map] = try_as_array(values)?;
+    let bindings = map.as_map()?;
+    evaluate(value, &bindings)
+}
+
+pub fn define_all(module: &mut Module) {
+    module.set_native("simplify", simplify_, 1);
+    module.set_native("diff", diff_, 2);
+    module.set_native("subst", subst_, 2..);
+    module.set_native("evaluate", evaluate_, 2);
+}
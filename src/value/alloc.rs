@@ -0,0 +1,14 @@
+use std::cell::Cell;
+
+// Counts heap objects as they are created, for `Limits::max_allocations`. Sizes aren't tracked.
+thread_local! {
+    static ALLOCATION_COUNT: Cell<usize> = const { Cell::new(0) };
+}
+
+pub fn record() {
+    ALLOCATION_COUNT.with(|count| count.set(count.get().wrapping_add(1)));
+}
+
+pub fn count() -> usize {
+    ALLOCATION_COUNT.with(Cell::get)
+}
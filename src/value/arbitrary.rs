@@ -0,0 +1,128 @@
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_rational::BigRational;
+use proptest::{collection::vec, prelude::*, sample::select};
+
+use crate::{Symbol, Value, value::Compound};
+
+const FLOATS: [f64; 9] = [
+    0.0,
+    -0.0,
+    0.5,
+    1.0,
+    -2.0,
+    1e300,
+    f64::INFINITY,
+    f64::NEG_INFINITY,
+    f64::NAN,
+];
+
+fn symbol() -> impl Strategy<Value = Symbol> {
+    select(vec!["nil", "true", "false", "a", "b", "point", "cons"]).prop_map(Symbol::new)
+}
+
+fn compound_type() -> impl Strategy<Value = Symbol> {
+    select(vec![
+        "point",
+        "add",
+        "a",
+        "cons",
+        "quote",
+        "quasiquote",
+        "unquote",
+        "unquote-splicing",
+    ])
+    .prop_map(Symbol::new)
+}
+
+fn finite_number() -> impl Strategy<Value = Value> {
+    let floats = FLOATS.iter().copied().filter(|num| num.is_finite());
+    prop_oneof![
+        (-3i64..=3).prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        (any::<i64>(), 1u32..4).prop_map(|(num, pow)| Value::from(BigInt::from(num).pow(pow))),
+        (-4i64..=4, 1i64..=4).prop_map(|(numer, denom)| BigRational::new(
+            numer.into(),
+            denom.into()
+        )
+        .into()),
+        select(floats.clone().collect::<Vec<_>>()).prop_map(Value::from),
+        any::<f64>()
+            .prop_filter("finite", |num| num.is_finite())
+            .prop_map(Value::from),
+        (
+            select(floats.clone().collect::<Vec<_>>()),
+            select(floats.collect::<Vec<_>>())
+        )
+            .prop_map(|(re, im)| Complex64::new(re, im).into()),
+    ]
+}
+
+fn number() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        (-3i64..=3).prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        (any::<i64>(), 1u32..4).prop_map(|(num, pow)| Value::from(BigInt::from(num).pow(pow))),
+        (-4i64..=4, 1i64..=4).prop_map(|(numer, denom)| BigRational::new(
+            numer.into(),
+            denom.into()
+        )
+        .into()),
+        select(FLOATS.to_vec()).prop_map(Value::from),
+        any::<f64>().prop_map(Value::from),
+        (select(FLOATS.to_vec()), select(FLOATS.to_vec()))
+            .prop_map(|(re, im)| Complex64::new(re, im).into()),
+    ]
+}
+
+fn compound(inner: impl Strategy<Value = Value> + Clone) -> impl Strategy<Value = Value> {
+    prop_oneof![
+        compound_type().prop_flat_map({
+            let inner = inner.clone();
+            move |type_| {
+                let len = Compound::fixed_arity(type_).map_or(0..4, |arity| arity..arity + 1);
+                vec(inner.clone(), len).prop_map(move |values| Value::compound(type_, values))
+            }
+        }),
+        vec(inner.clone(), 0..4).prop_map(|values| Value::list(&values)),
+        inner.clone().prop_map(Value::quote),
+        vec(inner.clone(), 0..4).prop_map(Value::vector),
+        vec((inner.clone(), inner.clone()), 0..4).prop_map(Value::map),
+        vec(inner, 0..4).prop_map(Value::set),
+    ]
+}
+
+// Values whose printed form reads back as an equal value.
+pub fn printable() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        finite_number(),
+        symbol().prop_map(Value::from),
+        symbol().prop_map(Value::Keyword),
+        any::<char>().prop_map(Value::from),
+        vec(any::<u8>(), 0..4).prop_map(Value::from),
+        "[a-c \\\\\"]{0,3}".prop_map(Value::from),
+    ];
+
+    leaf.prop_recursive(3, 24, 4, compound)
+}
+
+pub fn value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        number(),
+        symbol().prop_map(Value::from),
+        "[a-c]{0,2}".prop_map(Value::from),
+        symbol().prop_map(Value::Keyword),
+        select(vec!['a', 'b', ' ']).prop_map(Value::from),
+        vec(0u8..3, 0..3).prop_map(Value::from),
+        (0u64..3).prop_map(Value::LogicVar),
+        (0u32..3).prop_map(Value::NativeFunction),
+    ];
+
+    leaf.prop_recursive(3, 24, 4, |inner| {
+        prop_oneof![
+            4 => compound(inner.clone()),
+            1 => inner.clone().prop_map(Value::boxed),
+            1 => (0u32..2, vec(inner, 0..3)).prop_map(|(fn_id, values)| Value::closure(fn_id, values)),
+        ]
+    })
+}
@@ -0,0 +1,53 @@
+use std::{
+    fmt::{self, Display, Formatter, Write},
+    ops::Deref,
+};
+
+use dumpster::{Trace, unsync::Gc};
+
+use crate::{Value, value::alloc};
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Bytes(pub Vec<u8>);
+
+unsafe impl Trace for Bytes {
+    fn accept<V: dumpster::Visitor>(&self, _visitor: &mut V) -> std::result::Result<(), ()> {
+        Ok(())
+    }
+}
+
+impl Deref for Bytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Display for Bytes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "#b\"")?;
+
+        for &byte in &self.0 {
+            match byte {
+                b'"' => write!(f, "\\\"")?,
+                b'\\' => write!(f, "\\\\")?,
+                b'\0' => write!(f, "\\0")?,
+                b'\n' => write!(f, "\\n")?,
+                b'\r' => write!(f, "\\r")?,
+                b'\t' => write!(f, "\\t")?,
+                b' '..=b'~' => f.write_char(byte.into())?,
+                _ => write!(f, "\\x{byte:02x}")?,
+            }
+        }
+
+        write!(f, "\"")
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(bytes: Vec<u8>) -> Self {
+        alloc::record();
+        Value::Bytes(Gc::new(Bytes(bytes)))
+    }
+}
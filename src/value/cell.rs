@@ -0,0 +1,119 @@
+use std::{
+    cell::RefCell,
+    fmt::{self, Debug, Formatter},
+};
+
+use dumpster::{Trace, unsync::Gc};
+
+use crate::{Value, value::alloc};
+
+#[derive(Trace)]
+pub struct Cell {
+    value: RefCell<Value>,
+}
+
+impl Cell {
+    pub fn get(&self) -> Value {
+        self.value.borrow().clone()
+    }
+
+    pub fn set(&self, value: Value) {
+        let old = self.value.replace(value);
+        // Replacing a collection by one derived from it is how a box is usually updated, so
+        // the old one is dropped first to let the new one be traced.
+        drop(old);
+        refresh(&self.value.borrow());
+    }
+}
+
+// A collection in a box may be part of a cycle, so it's traced again if it no longer shares
+// nodes with another collection.
+fn refresh(value: &Value) {
+    match value {
+        Value::Vector(values) => values.refresh(),
+        Value::Map(entries) => entries.refresh(),
+        Value::Set(values) => values.refresh(),
+        _ => {}
+    }
+}
+
+impl Debug for Cell {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cell").finish_non_exhaustive()
+    }
+}
+
+impl Value {
+    #[must_use]
+    pub fn boxed(value: Value) -> Self {
+        refresh(&value);
+        let cell = Cell {
+            value: RefCell::new(value),
+        };
+        alloc::record();
+        Value::Box(Gc::new(cell))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dumpster::{Trace, Visitor, sync, unsync};
+
+    use crate::Value;
+
+    #[derive(Default)]
+    struct CountVisitor(usize);
+
+    impl Visitor for CountVisitor {
+        fn visit_sync<T: Trace + Send + Sync + ?Sized>(&mut self, _gc: &sync::Gc<T>) {}
+
+        fn visit_unsync<T: Trace + ?Sized>(&mut self, _gc: &unsync::Gc<T>) {
+            self.0 += 1;
+        }
+    }
+
+    #[test]
+    fn trace_cycle() {
+        let cell = Value::boxed(Value::nil());
+        let Value::Box(gc) = &cell else {
+            unreachable!()
+        };
+
+        let string = Value::from("in the cycle".to_string());
+        let Value::String(string_gc) = &string else {
+            unreachable!()
+        };
+        gc.set(Value::list([cell.clone(), string.clone()]));
+
+        let mut visitor = CountVisitor::default();
+        gc.accept(&mut visitor).unwrap();
+        assert_eq!(1, visitor.0);
+
+        // The string is only released once the box and the list that holds it are collected.
+        assert_eq!(2, string_gc.ref_count().get());
+        drop(cell);
+        unsync::collect();
+        assert_eq!(1, string_gc.ref_count().get());
+    }
+
+    #[test]
+    fn derived_cycle() {
+        let string = Value::from("in the cycle".to_string());
+        let Value::String(string_gc) = &string else {
+            unreachable!()
+        };
+        let cell = Value::boxed(Value::vector([string.clone()]));
+        let Value::Box(gc) = &cell else {
+            unreachable!()
+        };
+
+        // As `swap!` does, replace the box's vector by one derived from it.
+        let mut values = (*gc.get().as_vector().unwrap()).clone();
+        values.push_back(cell.clone());
+        gc.set(values.into());
+
+        drop(cell);
+        unsync::collect();
+        assert_eq!(1, string_gc.ref_count().get());
+    }
+}
@@ -1,17 +1,74 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
 use anyhow::anyhow;
 use dumpster::Trace;
 
-use crate::{Result, Symbol, Value, symbol, try_as_array};
+use crate::{Result, Symbol, Value, symbol, try_as_array, value::hash::ValueHasher};
 
-#[derive(Clone, PartialEq, Debug, Trace)]
+#[derive(Clone, Debug, Trace)]
 pub struct Compound {
     pub type_: Symbol,
     pub values: Vec<Value>,
+    // Cached structural hash, present only for interned compounds.
+    hash: Option<u64>,
+    // Source line of a list read by `parser::source`, used for line breakpoints.
+    line: Option<u32>,
+}
+
+impl PartialEq for Compound {
+    fn eq(&self, other: &Self) -> bool {
+        self.type_ == other.type_ && self.values == other.values
+    }
 }
 
 impl Compound {
     pub fn new(type_: Symbol, values: Vec<Value>) -> Self {
-        Compound { type_, values }
+        Compound {
+            type_,
+            values,
+            hash: None,
+            line: None,
+        }
+    }
+
+    pub(crate) fn interned(type_: Symbol, values: Vec<Value>) -> Self {
+        let mut compound = Compound::new(type_, values);
+        compound.hash = Some(compound.structural_hash());
+        compound
+    }
+
+    pub fn is_interned(&self) -> bool {
+        self.hash.is_some()
+    }
+
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    // A copy of this compound recorded as read from `line`. Located compounds aren't interned,
+    // since identical forms on different lines must stay distinct.
+    pub(crate) fn at_line(&self, line: u32) -> Self {
+        Compound {
+            type_: self.type_,
+            values: self.values.clone(),
+            hash: None,
+            line: Some(line),
+        }
+    }
+
+    // Hashed with a fixed hasher so interned compounds can cache the result; nested compounds
+    // contribute their own structural hash, which is O(1) for interned children.
+    pub fn structural_hash(&self) -> u64 {
+        if let Some(hash) = self.hash {
+            return hash;
+        }
+
+        let mut state = DefaultHasher::new();
+        state.write_symbol(self.type_);
+        for value in &self.values {
+            value.hash(&mut state);
+        }
+        state.finish()
     }
 
     pub fn len(&self) -> usize {
@@ -22,6 +79,34 @@ impl Compound {
         self.len() == 0
     }
 
+    // Compounds the reader and printer treat specially have a fixed number of arguments.
+    pub fn fixed_arity(type_: Symbol) -> Option<usize> {
+        if type_ == *symbol::CONS {
+            Some(2)
+        } else if [
+            *symbol::QUOTE,
+            *symbol::QUASIQUOTE,
+            *symbol::UNQUOTE,
+            *symbol::UNQUOTE_SPLICING,
+        ]
+        .contains(&type_)
+        {
+            Some(1)
+        } else {
+            None
+        }
+    }
+
+    pub fn check_arity(type_: Symbol, len: usize) -> Result<()> {
+        match Compound::fixed_arity(type_) {
+            Some(arity) if arity != len => Err(anyhow!(
+                "#{type_} takes {arity} argument{}, got {len}",
+                if arity == 1 { "" } else { "s" }
+            )),
+            _ => Ok(()),
+        }
+    }
+
     pub fn has_type(&self, type_: Symbol) -> bool {
         self.type_ == type_
     }
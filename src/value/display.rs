@@ -1,7 +1,5 @@
 use std::fmt::{self, Debug, Display, Formatter};
 
-use dumpster::unsync::Gc;
-
 use crate::{Value, value::Compound};
 
 impl Compound {
@@ -18,13 +16,20 @@ impl Compound {
     fn fmt_list(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "(")?;
 
-        let list = Value::Compound(Gc::new(self.clone()));
-        for (i, value) in list.into_iter().enumerate() {
-            if i > 0 {
-                write!(f, " ")?;
-            }
+        let [head, tail] = self.as_array().unwrap();
+        write!(f, "{head}")?;
+
+        let mut tail = tail;
+        while let Value::Compound(cons) = tail
+            && cons.is_cons()
+        {
+            let [head, next] = cons.as_array().unwrap();
+            write!(f, " {head}")?;
+            tail = next;
+        }
 
-            write!(f, "{value}")?;
+        if !tail.is_nil() {
+            write!(f, " . {tail}")?;
         }
 
         write!(f, ")")
@@ -73,15 +78,61 @@ impl Display for Value {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Value::Symbol(sym) => Display::fmt(sym, f),
-            Value::Number(num) => {
-                if num.fract() == 0.0 {
-                    write!(f, "{num:.0}")
-                } else {
-                    write!(f, "{num}")
+            Value::Keyword(sym) => write!(f, ":{sym}"),
+            &Value::Char(c) => match c {
+                ' ' => write!(f, "#\\space"),
+                '\n' => write!(f, "#\\newline"),
+                '\t' => write!(f, "#\\tab"),
+                '\r' => write!(f, "#\\return"),
+                '\0' => write!(f, "#\\nul"),
+                _ if c.is_control() || c.is_whitespace() => {
+                    write!(f, "#\\u{{{:x}}}", u32::from(c))
                 }
-            }
+                _ => write!(f, "#\\{c}"),
+            },
+            Value::Integer(num) => Display::fmt(num, f),
+            Value::BigInteger(num) => Display::fmt(&**num, f),
+            Value::Rational(num) => Display::fmt(&**num, f),
+            Value::Number(num) => Debug::fmt(num, f),
+            Value::Complex(num) => Display::fmt(&**num, f),
             Value::String(s) => Debug::fmt(&**s, f),
+            Value::Bytes(bytes) => Display::fmt(&**bytes, f),
+            Value::Vector(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+
+                    write!(f, "{value}")?;
+                }
+                write!(f, "]")
+            }
+            Value::Map(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.sorted().into_iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+
+                    write!(f, "{key} {value}")?;
+                }
+                write!(f, "}}")
+            }
+            Value::Set(values) => {
+                write!(f, "#{{")?;
+                for (i, value) in values.sorted().into_iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+
+                    write!(f, "{value}")?;
+                }
+                write!(f, "}}")
+            }
             Value::Compound(compound) => Display::fmt(&**compound, f),
+            Value::Box(_) => write!(f, "(#box ...)"),
+            Value::LogicVar(id) => write!(f, "(#lvar {id})"),
             Value::Closure(fn_) => Display::fmt(&**fn_, f),
             &Value::NativeFunction(fn_id) => {
                 write!(f, "(#native-fn {fn_id})")
@@ -94,9 +145,21 @@ impl Debug for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Value::Symbol(sym) => f.debug_tuple("Symbol").field(sym).finish(),
+            Value::Keyword(sym) => f.debug_tuple("Keyword").field(sym).finish(),
+            Value::Char(c) => f.debug_tuple("Char").field(c).finish(),
+            Value::Integer(num) => f.debug_tuple("Integer").field(num).finish(),
+            Value::BigInteger(num) => f.debug_tuple("BigInteger").field(&num.0).finish(),
+            Value::Rational(num) => f.debug_tuple("Rational").field(&num.0).finish(),
             Value::Number(num) => f.debug_tuple("Number").field(num).finish(),
+            Value::Complex(num) => f.debug_tuple("Complex").field(&num.0).finish(),
             Value::String(string) => f.debug_tuple("String").field(&**string).finish(),
+            Value::Bytes(bytes) => f.debug_tuple("Bytes").field(&bytes.0).finish(),
+            Value::Vector(values) => f.debug_tuple("Vector").field(&***values).finish(),
+            Value::Map(entries) => f.debug_tuple("Map").field(&***entries).finish(),
+            Value::Set(values) => f.debug_tuple("Set").field(&***values).finish(),
             Value::Compound(compound) => f.debug_tuple("Compound").field(&**compound).finish(),
+            Value::Box(cell) => f.debug_tuple("Box").field(&**cell).finish(),
+            Value::LogicVar(id) => f.debug_tuple("LogicVar").field(id).finish(),
             Value::Closure(closure) => f.debug_tuple("Closure").field(&**closure).finish(),
             Value::NativeFunction(fn_id) => f.debug_tuple("NativeFunction").field(fn_id).finish(),
         }
@@ -1,6 +1,11 @@
 use dumpster::unsync::Gc;
 
-use crate::{FnId, Symbol, Value, function::Closure, symbol, value::Compound};
+use crate::{
+    FnId, Symbol, Value,
+    function::Closure,
+    symbol,
+    value::{Compound, alloc, intern},
+};
 
 impl From<Symbol> for Value {
     fn from(sym: Symbol) -> Self {
@@ -14,6 +19,18 @@ impl From<bool> for Value {
     }
 }
 
+impl From<char> for Value {
+    fn from(c: char) -> Self {
+        Value::Char(c)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(num: i64) -> Self {
+        Value::Integer(num)
+    }
+}
+
 impl From<f64> for Value {
     fn from(num: f64) -> Self {
         Value::Number(num)
@@ -22,6 +39,7 @@ impl From<f64> for Value {
 
 impl From<String> for Value {
     fn from(s: String) -> Self {
+        alloc::record();
         Value::String(Gc::new(s))
     }
 }
@@ -31,15 +49,24 @@ impl Value {
         Symbol::new(s).into()
     }
 
+    pub fn keyword<S: AsRef<str>>(s: S) -> Self {
+        Value::Keyword(Symbol::new(s))
+    }
+
     #[must_use]
     pub fn compound(type_: Symbol, values: Vec<Value>) -> Self {
-        let compound = Compound { type_, values };
-        Value::Compound(Gc::new(compound))
+        if intern::is_enabled() {
+            return Value::Compound(intern::intern(type_, values));
+        }
+
+        alloc::record();
+        Value::Compound(Gc::new(Compound::new(type_, values)))
     }
 
     #[must_use]
     pub fn closure(fn_id: FnId, values: Vec<Value>) -> Self {
-        let closure = Closure { fn_id, values };
+        let closure = Closure::new(fn_id, values);
+        alloc::record();
         Value::Closure(Gc::new(closure))
     }
 
@@ -57,6 +84,16 @@ impl Value {
         list
     }
 
+    pub fn list_with_tail<T: AsRef<[Value]>>(values: T, tail: Value) -> Self {
+        let mut list = tail;
+
+        for value in values.as_ref().iter().rev() {
+            list = Value::cons(value.clone(), list);
+        }
+
+        list
+    }
+
     pub fn quote(value: Value) -> Self {
         Value::compound(*symbol::QUOTE, vec![value])
     }
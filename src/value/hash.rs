@@ -1,5 +1,7 @@
 use std::hash::{Hash, Hasher};
 
+use dumpster::unsync::Gc;
+
 use crate::{Symbol, Value, symbol};
 
 pub trait ValueHasher: Hasher {
@@ -18,14 +20,74 @@ impl Hash for Value {
                 state.write_symbol(*symbol::SYMBOL);
                 state.write_symbol(sym);
             }
+            &Value::Keyword(sym) => {
+                state.write_symbol(*symbol::KEYWORD);
+                state.write_symbol(sym);
+            }
+            &Value::Char(c) => {
+                state.write_symbol(*symbol::CHAR);
+                state.write_u32(c.into());
+            }
+            &Value::Integer(num) => {
+                state.write_symbol(*symbol::INTEGER);
+                state.write_i64(num);
+            }
+            Value::BigInteger(num) => {
+                state.write_symbol(*symbol::INTEGER);
+                state.write(&num.to_signed_bytes_le());
+            }
+            Value::Rational(num) => {
+                state.write_symbol(*symbol::RATIONAL);
+                state.write(&num.numer().to_signed_bytes_le());
+                state.write(&num.denom().to_signed_bytes_le());
+            }
             &Value::Number(num) => {
-                state.write_symbol(*symbol::NUMBER);
+                state.write_symbol(*symbol::FLOAT);
                 state.write_u64(num.to_bits());
             }
+            Value::Complex(num) => {
+                state.write_symbol(*symbol::COMPLEX);
+                state.write_u64(num.re.to_bits());
+                state.write_u64(num.im.to_bits());
+            }
             Value::String(s) => {
                 state.write_symbol(*symbol::STRING);
                 state.write(s.as_bytes());
             }
+            Value::Bytes(bytes) => {
+                state.write_symbol(*symbol::BYTES);
+                state.write(bytes);
+            }
+            Value::Vector(values) => {
+                state.write_symbol(*symbol::VECTOR);
+                state.write_usize(values.len());
+                for value in values.iter() {
+                    value.hash(state);
+                }
+            }
+            Value::Map(entries) => {
+                state.write_symbol(*symbol::MAP);
+                state.write_usize(entries.len());
+                for (key, value) in entries.sorted() {
+                    key.hash(state);
+                    value.hash(state);
+                }
+            }
+            Value::Set(values) => {
+                state.write_symbol(*symbol::SET);
+                state.write_usize(values.len());
+                for value in values.sorted() {
+                    value.hash(state);
+                }
+            }
+            Value::Box(cell) => {
+                state.write_symbol(*symbol::BOX);
+                Gc::as_ptr(cell).hash(state);
+            }
+            &Value::LogicVar(id) => {
+                state.write_symbol(*symbol::LVAR);
+                state.write_u64(id);
+            }
             Value::Closure(closure) => {
                 state.write_symbol(*symbol::FN);
                 state.write_u32(closure.fn_id);
@@ -34,12 +96,7 @@ impl Hash for Value {
                 state.write_symbol(*symbol::NATIVE_FN);
                 state.write_u32(fn_id);
             }
-            Value::Compound(compound) => {
-                state.write_symbol(compound.type_);
-                for value in &compound.values {
-                    value.hash(state);
-                }
-            }
+            Value::Compound(compound) => state.write_u64(compound.structural_hash()),
         }
     }
 }
@@ -0,0 +1,186 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use dumpster::unsync::Gc;
+
+use crate::{
+    Symbol, Value,
+    value::{Compound, alloc},
+};
+
+// Interned compounds bucketed by structural hash. `dumpster`'s unsync `Gc` has no weak
+// pointers, so the table holds strong references and `sweep` releases the terms nothing else
+// holds. Sweeps run when the table has doubled in size since the last one.
+struct Table {
+    buckets: HashMap<u64, Vec<Gc<Compound>>>,
+    len: usize,
+    sweep_at: usize,
+}
+
+const MIN_SWEEP_AT: usize = 1024;
+
+impl Default for Table {
+    fn default() -> Self {
+        Table {
+            buckets: HashMap::new(),
+            len: 0,
+            sweep_at: MIN_SWEEP_AT,
+        }
+    }
+}
+
+// The table is per thread rather than per VM: every VM on a thread shares it, so enabling or
+// clearing it in one affects the others.
+thread_local! {
+    static TABLE: RefCell<Option<Table>> = const { RefCell::new(None) };
+}
+
+// While enabled, `Value::compound` (and so `Inst::Compound` and `Inst::List`) returns a shared
+// instance for structurally identical terms, which makes comparing equal interned compounds a
+// pointer comparison.
+pub fn enable() {
+    TABLE.with(|table| {
+        table.borrow_mut().get_or_insert_default();
+    });
+}
+
+pub fn disable() {
+    // Take the table out first so dropping its terms can't reenter the borrow.
+    let table = TABLE.with(RefCell::take);
+    drop(table);
+}
+
+#[must_use]
+pub fn is_enabled() -> bool {
+    TABLE.with(|table| table.borrow().is_some())
+}
+
+// Releases the interned terms, keeping interning enabled. Terms still in use stay valid but
+// are no longer shared with new ones.
+pub fn clear() {
+    let buckets = TABLE.with(|table| {
+        table.borrow_mut().as_mut().map(|table| {
+            table.len = 0;
+            std::mem::take(&mut table.buckets)
+        })
+    });
+    drop(buckets);
+}
+
+// Releases the interned terms that only the table holds. Releasing a term can leave its
+// children held only by the table, so this repeats until nothing is released.
+pub fn sweep() {
+    loop {
+        let released = TABLE.with(|table| {
+            let mut table = table.borrow_mut();
+            let Some(table) = table.as_mut() else {
+                return Vec::new();
+            };
+
+            let mut released = Vec::new();
+            table.buckets.retain(|_, bucket| {
+                let (held, unheld): (Vec<_>, Vec<_>) = std::mem::take(bucket)
+                    .into_iter()
+                    .partition(|compound| Gc::ref_count(compound).get() > 1);
+                *bucket = held;
+                released.extend(unheld);
+                !bucket.is_empty()
+            });
+            table.len -= released.len();
+            table.sweep_at = MIN_SWEEP_AT.max(table.len * 2);
+            released
+        });
+        if released.is_empty() {
+            return;
+        }
+        // Dropped outside the borrow, like `disable`.
+        drop(released);
+    }
+}
+
+#[must_use]
+pub fn len() -> usize {
+    TABLE.with(|table| table.borrow().as_ref().map_or(0, |table| table.len))
+}
+
+pub(crate) fn intern(type_: Symbol, values: Vec<Value>) -> Gc<Compound> {
+    // Children are interned before their parents, so comparing equal children stops at the
+    // pointer check.
+    let compound = Compound::interned(type_, values);
+    let hash = compound.structural_hash();
+    let (compound, full) = TABLE.with(|table| {
+        let mut table = table.borrow_mut();
+        let table = table.get_or_insert_default();
+        let bucket = table.buckets.entry(hash).or_default();
+        if let Some(existing) = bucket.iter().find(|existing| ***existing == compound) {
+            return (existing.clone(), false);
+        }
+
+        alloc::record();
+        let compound = Gc::new(compound);
+        bucket.push(compound.clone());
+        table.len += 1;
+        (compound, table.len >= table.sweep_at)
+    });
+    if full {
+        sweep();
+    }
+    compound
+}
+
+#[cfg(test)]
+mod tests {
+    use dumpster::unsync::Gc;
+
+    use crate::{Module, Symbol, VM, Value};
+
+    #[test]
+    fn interning() {
+        super::enable();
+        let term = || {
+            Value::compound(
+                Symbol::new("f"),
+                vec![Value::list([1.into(), 2.into()]), Value::symbol("x")],
+            )
+        };
+        let (Value::Compound(a), Value::Compound(b)) = (term(), term()) else {
+            unreachable!();
+        };
+        assert!(Gc::ptr_eq(&a, &b));
+        assert_eq!(3, super::len());
+
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+        let value = module.eval_str("#f((list 1 2) 'x)").unwrap();
+        assert!(value.eqv(&Value::Compound(a.clone())));
+        drop(value);
+
+        // Terms interned since a clear aren't shared with earlier ones but still compare equal.
+        super::clear();
+        let Value::Compound(d) = term() else {
+            unreachable!();
+        };
+        assert!(!Gc::ptr_eq(&a, &d));
+        assert_eq!(Value::Compound(a.clone()), Value::Compound(d.clone()));
+
+        // Sweeping releases `d` once it's dropped, then its children.
+        assert_eq!(3, super::len());
+        super::sweep();
+        assert_eq!(3, super::len());
+        drop(d);
+        super::sweep();
+        assert_eq!(0, super::len());
+
+        // Terms that aren't kept don't accumulate.
+        for i in 0..5000 {
+            let _ = Value::compound(Symbol::new("g"), vec![i.into()]);
+        }
+        assert!(super::len() < super::MIN_SWEEP_AT);
+
+        super::disable();
+        let Value::Compound(c) = term() else {
+            unreachable!();
+        };
+        assert!(!c.is_interned());
+        assert_eq!(Value::Compound(c), Value::Compound(b));
+    }
+}
@@ -1,7 +1,11 @@
 use anyhow::anyhow;
 use dumpster::unsync::Gc;
 
-use crate::{Error, FnId, Result, Symbol, Value, value::Compound};
+use crate::{
+    Error, FnId, Result, Symbol, Value,
+    error::expected,
+    value::{Bytes, Cell, Compound, Map, Set, Vector},
+};
 
 impl TryInto<Symbol> for Value {
     type Error = Error;
@@ -11,6 +15,14 @@ impl TryInto<Symbol> for Value {
     }
 }
 
+impl TryInto<i64> for Value {
+    type Error = Error;
+
+    fn try_into(self) -> Result<i64> {
+        Value::as_integer(&self)
+    }
+}
+
 impl TryInto<f64> for Value {
     type Error = Error;
 
@@ -24,7 +36,7 @@ impl Value {
         if let &Value::Symbol(sym) = self {
             Ok(sym)
         } else {
-            Err(anyhow!("expected symbol, got {self}"))
+            Err(expected("symbol", self))
         }
     }
 
@@ -34,15 +46,22 @@ impl Value {
         } else if self == &Value::false_() {
             Ok(false)
         } else {
-            Err(anyhow!("expected true or false, got {self}"))
+            Err(expected("true or false", self))
+        }
+    }
+
+    pub fn as_integer(&self) -> Result<i64> {
+        match *self {
+            Value::Integer(num) => Ok(num),
+            Value::BigInteger(_) => Err(anyhow!("integer {self} is out of range")),
+            _ => Err(expected("integer", self)),
         }
     }
 
     pub fn as_number(&self) -> Result<f64> {
-        if let &Value::Number(num) = self {
-            Ok(num)
-        } else {
-            Err(anyhow!("expected number, got {self}"))
+        match self {
+            Value::Complex(_) => Err(expected("real number", self)),
+            _ => self.to_f64().ok_or_else(|| expected("number", self)),
         }
     }
 
@@ -50,7 +69,72 @@ impl Value {
         if let Value::String(s) = self {
             Ok(s.clone())
         } else {
-            Err(anyhow!("expected string, got {self}"))
+            Err(expected("string", self))
+        }
+    }
+
+    pub fn as_vector(&self) -> Result<Gc<Vector>> {
+        if let Value::Vector(values) = self {
+            Ok(values.clone())
+        } else {
+            Err(expected("vector", self))
+        }
+    }
+
+    // The items of a list or vector, for arguments that may be written as either.
+    pub fn as_items(&self) -> Result<Vec<Value>> {
+        if self.is_proper_list() || matches!(self, Value::Vector(_)) {
+            Ok(self.iter().cloned().collect())
+        } else {
+            Err(expected("list or vector", self))
+        }
+    }
+
+    pub fn as_map(&self) -> Result<Gc<Map>> {
+        if let Value::Map(entries) = self {
+            Ok(entries.clone())
+        } else {
+            Err(expected("map", self))
+        }
+    }
+
+    pub fn as_set(&self) -> Result<Gc<Set>> {
+        if let Value::Set(values) = self {
+            Ok(values.clone())
+        } else {
+            Err(expected("set", self))
+        }
+    }
+
+    pub fn as_char(&self) -> Result<char> {
+        if let &Value::Char(c) = self {
+            Ok(c)
+        } else {
+            Err(expected("char", self))
+        }
+    }
+
+    pub fn as_keyword(&self) -> Result<Symbol> {
+        if let &Value::Keyword(sym) = self {
+            Ok(sym)
+        } else {
+            Err(expected("keyword", self))
+        }
+    }
+
+    pub fn as_bytes(&self) -> Result<Gc<Bytes>> {
+        if let Value::Bytes(bytes) = self {
+            Ok(bytes.clone())
+        } else {
+            Err(expected("bytes", self))
+        }
+    }
+
+    pub fn as_box(&self) -> Result<Gc<Cell>> {
+        if let Value::Box(cell) = self {
+            Ok(cell.clone())
+        } else {
+            Err(expected("box", self))
         }
     }
 
@@ -58,7 +142,7 @@ impl Value {
         if let Value::Compound(compound) = self {
             Ok(compound.clone())
         } else {
-            Err(anyhow!("expected compound, got {self}"))
+            Err(expected("compound", self))
         }
     }
 
@@ -66,7 +150,7 @@ impl Value {
         if let &Value::NativeFunction(fn_id) = self {
             Ok(fn_id)
         } else {
-            Err(anyhow!("expected native function, got {self}"))
+            Err(expected("native function", self))
         }
     }
 }
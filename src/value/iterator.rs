@@ -1,12 +1,16 @@
 use crate::Value;
 
-pub struct Iter<'a> {
-    value: &'a Value,
+pub enum Iter<'a> {
+    List(&'a Value),
+    Vector(im::vector::Iter<'a, Value>),
 }
 
 impl<'a> Iter<'a> {
     pub fn new(value: &'a Value) -> Self {
-        Iter { value }
+        match value {
+            Value::Vector(values) => Iter::Vector(values.iter()),
+            _ => Iter::List(value),
+        }
     }
 }
 
@@ -14,14 +18,17 @@ impl<'a> Iterator for Iter<'a> {
     type Item = &'a Value;
 
     fn next(&mut self) -> Option<&'a Value> {
-        match self.value {
-            Value::Compound(cons) if cons.is_cons() => {
-                let [head, tail] = cons.as_array().unwrap();
-                self.value = tail;
-                Some(head)
-            }
-            value if value.is_nil() => None,
-            _ => panic!("expected cons or nil, got {}", self.value),
+        match self {
+            Iter::List(value) => match value {
+                Value::Compound(cons) if cons.is_cons() => {
+                    let [head, tail] = cons.as_array().unwrap();
+                    *value = tail;
+                    Some(head)
+                }
+                value if value.is_nil() => None,
+                _ => panic!("expected cons or nil, got {value}"),
+            },
+            Iter::Vector(iter) => iter.next(),
         }
     }
 }
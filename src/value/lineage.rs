@@ -0,0 +1,50 @@
+use std::{cell::Cell, rc::Rc};
+
+// Tracks whether a persistent collection shares nodes with another live collection.
+//
+// `im` collections share nodes between versions, but the collector counts each `Gc` once no
+// matter how many collections reach it through a shared node. A collection that may share
+// nodes therefore doesn't report its values, which at worst keeps a cycle through it alive.
+// Every collection derived from another holds the same token, so once the others are dropped
+// its values can be reported again. That is only decided at points where no collection is
+// running, so every pass of a collection sees the same edges.
+#[derive(Debug)]
+pub struct Lineage {
+    token: Rc<()>,
+    traced: Cell<bool>,
+}
+
+impl Lineage {
+    pub fn new() -> Self {
+        Lineage {
+            token: Rc::new(()),
+            traced: Cell::new(true),
+        }
+    }
+
+    pub fn is_traced(&self) -> bool {
+        self.traced.get()
+    }
+
+    // Reports the values again if no other live collection shares this one's nodes.
+    pub fn refresh(&self) {
+        self.traced.set(Rc::strong_count(&self.token) == 1);
+    }
+}
+
+impl Default for Lineage {
+    fn default() -> Self {
+        Lineage::new()
+    }
+}
+
+// Cloning a collection shares its nodes, so neither copy reports its values.
+impl Clone for Lineage {
+    fn clone(&self) -> Self {
+        self.traced.set(false);
+        Lineage {
+            token: self.token.clone(),
+            traced: Cell::new(false),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::ops::{Deref, DerefMut};
+
+use dumpster::{Trace, unsync::Gc};
+
+use crate::{Value, value::alloc};
+
+use super::lineage::Lineage;
+
+// Like `Vector`, a map derived from another is made by cloning the `Map`.
+#[derive(Clone, Debug, Default)]
+pub struct Map {
+    entries: im::HashMap<Value, Value>,
+    lineage: Lineage,
+}
+
+unsafe impl Trace for Map {
+    fn accept<V: dumpster::Visitor>(&self, visitor: &mut V) -> std::result::Result<(), ()> {
+        if self.lineage.is_traced() {
+            for (key, value) in &self.entries {
+                key.accept(visitor)?;
+                value.accept(visitor)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl Deref for Map {
+    type Target = im::HashMap<Value, Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entries
+    }
+}
+
+impl DerefMut for Map {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.entries
+    }
+}
+
+impl Map {
+    pub fn sorted(&self) -> Vec<(&Value, &Value)> {
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort();
+        entries
+    }
+
+    pub(crate) fn refresh(&self) {
+        self.lineage.refresh();
+    }
+}
+
+// The entries must be in nodes of their own; see `Vector`.
+impl From<im::HashMap<Value, Value>> for Map {
+    fn from(entries: im::HashMap<Value, Value>) -> Self {
+        Map {
+            entries,
+            lineage: Lineage::new(),
+        }
+    }
+}
+
+impl From<Map> for Value {
+    fn from(map: Map) -> Self {
+        alloc::record();
+        Value::Map(Gc::new(map))
+    }
+}
+
+impl From<im::HashMap<Value, Value>> for Value {
+    fn from(entries: im::HashMap<Value, Value>) -> Self {
+        Map::from(entries).into()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Set {
+    values: im::HashSet<Value>,
+    lineage: Lineage,
+}
+
+unsafe impl Trace for Set {
+    fn accept<V: dumpster::Visitor>(&self, visitor: &mut V) -> std::result::Result<(), ()> {
+        if self.lineage.is_traced() {
+            for value in &self.values {
+                value.accept(visitor)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PartialEq for Set {
+    fn eq(&self, other: &Self) -> bool {
+        self.values == other.values
+    }
+}
+
+impl Deref for Set {
+    type Target = im::HashSet<Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.values
+    }
+}
+
+impl DerefMut for Set {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.values
+    }
+}
+
+impl Set {
+    pub fn sorted(&self) -> Vec<&Value> {
+        let mut values: Vec<_> = self.values.iter().collect();
+        values.sort();
+        values
+    }
+
+    pub(crate) fn refresh(&self) {
+        self.lineage.refresh();
+    }
+}
+
+// The values must be in nodes of their own; see `Vector`.
+impl From<im::HashSet<Value>> for Set {
+    fn from(values: im::HashSet<Value>) -> Self {
+        Set {
+            values,
+            lineage: Lineage::new(),
+        }
+    }
+}
+
+impl From<Set> for Value {
+    fn from(set: Set) -> Self {
+        alloc::record();
+        Value::Set(Gc::new(set))
+    }
+}
+
+impl From<im::HashSet<Value>> for Value {
+    fn from(values: im::HashSet<Value>) -> Self {
+        Set::from(values).into()
+    }
+}
+
+impl Value {
+    pub fn map<T: AsRef<[(Value, Value)]>>(entries: T) -> Self {
+        entries
+            .as_ref()
+            .iter()
+            .cloned()
+            .collect::<im::HashMap<_, _>>()
+            .into()
+    }
+
+    pub fn set<T: AsRef<[Value]>>(values: T) -> Self {
+        values
+            .as_ref()
+            .iter()
+            .cloned()
+            .collect::<im::HashSet<_>>()
+            .into()
+    }
+}
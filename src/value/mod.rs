@@ -1,30 +1,57 @@
+pub mod alloc;
+#[cfg(test)]
+pub(crate) mod arbitrary;
+mod bytes;
+mod cell;
 mod compound;
 mod display;
 mod from;
 mod hash;
+pub mod intern;
 mod into;
 mod iterator;
+mod lineage;
+mod map;
+mod number;
+mod pretty;
+mod vector;
 
 use std::cmp::Ordering;
 
+pub use bytes::Bytes;
+pub use cell::Cell;
 pub use compound::Compound;
+pub use map::{Map, Set};
+pub use number::{BigInteger, Complex, Rational};
+pub use pretty::Pretty;
+pub use vector::Vector;
 
 use dumpster::{Trace, unsync::Gc};
 
 use crate::{FnId, Symbol, function, symbol};
 
-#[derive(Clone, PartialEq, Trace)]
+#[derive(Clone, Trace)]
 pub enum Value {
     Symbol(Symbol),
+    Keyword(Symbol),
+    Char(char),
+    Integer(i64),
+    BigInteger(Gc<BigInteger>),
+    Rational(Gc<Rational>),
     Number(f64),
+    Complex(Gc<Complex>),
     String(Gc<String>),
+    Bytes(Gc<Bytes>),
+    Vector(Gc<Vector>),
+    Map(Gc<Map>),
+    Set(Gc<Set>),
     Compound(Gc<Compound>),
+    Box(Gc<Cell>),
+    LogicVar(u64),
     Closure(Gc<function::Closure>),
     NativeFunction(FnId),
 }
 
-impl Eq for Value {}
-
 impl Value {
     #[must_use]
     pub fn nil() -> Value {
@@ -49,17 +76,92 @@ impl Value {
         self == &Value::true_() || self == &Value::false_()
     }
 
+    pub fn is_list(&self) -> bool {
+        match self {
+            Value::Compound(compound) => compound.is_cons(),
+            _ => self.is_nil(),
+        }
+    }
+
+    // Unlike `is_list`, this walks the whole spine so dotted lists like `(a . b)` are rejected.
+    pub fn is_proper_list(&self) -> bool {
+        let mut value = self;
+        while let Value::Compound(cons) = value
+            && cons.is_cons()
+        {
+            value = &cons.values[1];
+        }
+        value.is_nil()
+    }
+
+    pub fn is_number(&self) -> bool {
+        self.is_exact() || matches!(self, Value::Number(_) | Value::Complex(_))
+    }
+
     pub fn is_truthy(&self) -> bool {
         *self != Value::false_()
     }
 
+    // Identity rather than structural equality for heap-allocated strings, compounds and
+    // closures; numbers must agree in both value and exactness.
+    pub fn eqv(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::String(a), Value::String(b)) => Gc::ptr_eq(a, b),
+            (Value::Bytes(a), Value::Bytes(b)) => Gc::ptr_eq(a, b),
+            (Value::Vector(a), Value::Vector(b)) => Gc::ptr_eq(a, b),
+            (Value::Map(a), Value::Map(b)) => Gc::ptr_eq(a, b),
+            (Value::Set(a), Value::Set(b)) => Gc::ptr_eq(a, b),
+            (Value::Compound(a), Value::Compound(b)) => Gc::ptr_eq(a, b),
+            (Value::Closure(a), Value::Closure(b)) => Gc::ptr_eq(a, b),
+            _ => self == other,
+        }
+    }
+
+    pub fn numeric_cmp(&self, other: &Value) -> Option<Ordering> {
+        if self.is_number() && other.is_number() {
+            number::cmp_numbers(self, other)
+        } else {
+            None
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Value::Char(_) => 1,
+            Value::Symbol(_) => 2,
+            Value::Keyword(_) => 3,
+            Value::String(_) => 4,
+            Value::Bytes(_) => 5,
+            Value::Vector(_) => 6,
+            Value::Map(_) => 7,
+            Value::Set(_) => 8,
+            Value::Compound(_) => 9,
+            Value::Box(_) => 10,
+            Value::LogicVar(_) => 11,
+            Value::Closure(_) => 12,
+            Value::NativeFunction(_) => 13,
+            _ => 0,
+        }
+    }
+
     pub fn type_(&self) -> Symbol {
         match self {
             _ if self.is_nil() => *symbol::NIL,
             _ if self.is_boolean() => *symbol::BOOLEAN,
             Value::Symbol(_) => *symbol::SYMBOL,
-            Value::Number(_) => *symbol::NUMBER,
+            Value::Keyword(_) => *symbol::KEYWORD,
+            Value::Char(_) => *symbol::CHAR,
+            Value::Integer(_) | Value::BigInteger(_) => *symbol::INTEGER,
+            Value::Rational(_) => *symbol::RATIONAL,
+            Value::Number(_) => *symbol::FLOAT,
+            Value::Complex(_) => *symbol::COMPLEX,
             Value::String(_) => *symbol::STRING,
+            Value::Bytes(_) => *symbol::BYTES,
+            Value::Vector(_) => *symbol::VECTOR,
+            Value::Map(_) => *symbol::MAP,
+            Value::Set(_) => *symbol::SET,
+            Value::Box(_) => *symbol::BOX,
+            Value::LogicVar(_) => *symbol::LVAR,
             Value::Closure(_) => *symbol::FN,
             Value::NativeFunction(_) => *symbol::NATIVE_FN,
             Value::Compound(compound) => compound.type_,
@@ -67,16 +169,182 @@ impl Value {
     }
 }
 
+// Interned compounds that are equal are usually the same instance, which `cmp` checks first,
+// but terms interned before a `clear` or `disable` aren't shared with later ones.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Value {}
+
+// Total order: numbers < chars < symbols < keywords < strings < bytes < vectors < maps <
+// sets < compounds < boxes < logic variables < closures < native functions. Numbers are
+// ordered numerically (see `number::total_cmp`), symbols, keywords and strings by name,
+// chars by code point, bytes and vectors lexicographically, maps and sets by their sorted
+// entries, compounds by type name then arguments, boxes by identity, logic variables by id,
+// closures by function then captures (or identity for `letrec` closures, which may capture
+// themselves).
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Symbol(a), Value::Symbol(b)) | (Value::Keyword(a), Value::Keyword(b))
+                if a == b =>
+            {
+                Ordering::Equal
+            }
+            (Value::Symbol(a), Value::Symbol(b)) | (Value::Keyword(a), Value::Keyword(b)) => {
+                a.as_str().cmp(b.as_str())
+            }
+            (Value::Char(a), Value::Char(b)) => a.cmp(b),
+            _ if self.is_number() && other.is_number() => number::total_cmp(self, other),
+            (Value::String(a), Value::String(b)) => a.as_str().cmp(b.as_str()),
+            (Value::Bytes(a), Value::Bytes(b)) => a.0.cmp(&b.0),
+            (Value::Vector(a), Value::Vector(b)) if Gc::ptr_eq(a, b) => Ordering::Equal,
+            (Value::Vector(a), Value::Vector(b)) => a.iter().cmp(b.iter()),
+            (Value::Map(a), Value::Map(b)) if Gc::ptr_eq(a, b) => Ordering::Equal,
+            (Value::Map(a), Value::Map(b)) => a.sorted().cmp(&b.sorted()),
+            (Value::Set(a), Value::Set(b)) if Gc::ptr_eq(a, b) => Ordering::Equal,
+            (Value::Set(a), Value::Set(b)) => a.sorted().cmp(&b.sorted()),
+            (Value::Compound(a), Value::Compound(b)) if Gc::ptr_eq(a, b) => Ordering::Equal,
+            (Value::Compound(a), Value::Compound(b)) => Value::Symbol(a.type_)
+                .cmp(&Value::Symbol(b.type_))
+                .then_with(|| a.values.cmp(&b.values)),
+            (Value::Box(a), Value::Box(b)) => Gc::as_ptr(a).cmp(&Gc::as_ptr(b)),
+            (Value::LogicVar(a), Value::LogicVar(b)) => a.cmp(b),
+            (Value::Closure(a), Value::Closure(b)) if Gc::ptr_eq(a, b) => Ordering::Equal,
+            (Value::Closure(a), Value::Closure(b)) => a.fn_id.cmp(&b.fn_id).then_with(|| {
+                if a.is_recursive() || b.is_recursive() {
+                    Gc::as_ptr(a).cmp(&Gc::as_ptr(b))
+                } else {
+                    a.values().cmp(&b.values())
+                }
+            }),
+            (Value::NativeFunction(a), Value::NativeFunction(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
 impl PartialOrd for Value {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match (self, other) {
-            (Value::Symbol(a), Value::Symbol(b)) => a.partial_cmp(b),
-            (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
-            (Value::String(a), Value::String(b)) => a.partial_cmp(b),
-            (Value::Compound(a), Value::Compound(b)) if a.type_ == b.type_ => {
-                a.values.partial_cmp(&b.values)
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cmp::Ordering,
+        hash::{BuildHasher, RandomState},
+    };
+
+    use num_complex::Complex64;
+    use num_rational::BigRational;
+    use proptest::prelude::*;
+
+    use super::{Value, arbitrary};
+
+    fn deep_copy(value: &Value) -> Value {
+        match value {
+            Value::String(s) => s.to_string().into(),
+            Value::Bytes(bytes) => bytes.0.clone().into(),
+            Value::Vector(values) => values
+                .iter()
+                .map(deep_copy)
+                .collect::<im::Vector<_>>()
+                .into(),
+            Value::Map(entries) => entries
+                .iter()
+                .map(|(key, value)| (deep_copy(key), deep_copy(value)))
+                .collect::<im::HashMap<_, _>>()
+                .into(),
+            Value::Set(values) => values
+                .iter()
+                .map(deep_copy)
+                .collect::<im::HashSet<_>>()
+                .into(),
+            Value::Compound(compound) => Value::compound(
+                compound.type_,
+                compound.values.iter().map(deep_copy).collect(),
+            ),
+            Value::Closure(closure) => Value::closure(
+                closure.fn_id,
+                closure.values().iter().map(deep_copy).collect(),
+            ),
+            _ => value.clone(),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn equality_is_reflexive(a in arbitrary::value()) {
+            let b = deep_copy(&a);
+            let state = RandomState::new();
+            prop_assert_eq!(&a, &a);
+            prop_assert_eq!(&a, &b);
+            prop_assert_eq!(a.cmp(&b), Ordering::Equal);
+            prop_assert_eq!(state.hash_one(&a), state.hash_one(&b));
+        }
+
+        #[test]
+        fn order_agrees_with_equality_and_hash(a in arbitrary::value(), b in arbitrary::value()) {
+            let state = RandomState::new();
+            prop_assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+            prop_assert_eq!(a == b, a.cmp(&b) == Ordering::Equal);
+            prop_assert_eq!(a.partial_cmp(&b), Some(a.cmp(&b)));
+            if a == b {
+                prop_assert_eq!(state.hash_one(&a), state.hash_one(&b));
             }
-            _ => self.type_().partial_cmp(&other.type_()),
         }
+
+        #[test]
+        fn order_is_transitive(
+            a in arbitrary::value(),
+            b in arbitrary::value(),
+            c in arbitrary::value(),
+        ) {
+            let mut values = [a, b, c];
+            values.sort();
+            let [a, b, c] = &values;
+            prop_assert!(a <= b && b <= c && a <= c);
+            prop_assert_eq!(a.cmp(b).then(b.cmp(c)), a.cmp(c));
+        }
+
+        #[test]
+        fn eqv_implies_equal(a in arbitrary::value()) {
+            let b = deep_copy(&a);
+            prop_assert!(a.eqv(&a.clone()));
+            prop_assert!(!a.eqv(&b) || a == b);
+        }
+    }
+
+    #[test]
+    fn numbers() {
+        let half = BigRational::new(1.into(), 2.into()).into();
+        let values: Vec<Value> = vec![
+            f64::NEG_INFINITY.into(),
+            (-1).into(),
+            (-1.0).into(),
+            0.into(),
+            (-0.0).into(),
+            0.0.into(),
+            half,
+            0.5.into(),
+            1.into(),
+            1.0.into(),
+            Complex64::new(1.0, 0.0).into(),
+            Complex64::new(1.0, 1.0).into(),
+        ];
+        let mut sorted = values.clone();
+        sorted.reverse();
+        sorted.sort();
+        assert_eq!(values, sorted);
+
+        let nan = Value::from(f64::NAN);
+        assert_eq!(nan, nan.clone());
+        assert!(Value::from(f64::INFINITY) < nan);
+        assert_ne!(Value::from(0.0), Value::from(-0.0));
     }
 }
@@ -0,0 +1,234 @@
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+    ops::Deref,
+};
+
+use dumpster::{Trace, unsync::Gc};
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_rational::BigRational;
+use num_traits::ToPrimitive;
+
+use crate::{Value, value::alloc};
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct BigInteger(pub BigInt);
+
+unsafe impl Trace for BigInteger {
+    fn accept<V: dumpster::Visitor>(&self, _visitor: &mut V) -> std::result::Result<(), ()> {
+        Ok(())
+    }
+}
+
+impl Deref for BigInteger {
+    type Target = BigInt;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Display for BigInteger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Rational(pub BigRational);
+
+unsafe impl Trace for Rational {
+    fn accept<V: dumpster::Visitor>(&self, _visitor: &mut V) -> std::result::Result<(), ()> {
+        Ok(())
+    }
+}
+
+impl Deref for Rational {
+    type Target = BigRational;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.0.numer(), self.0.denom())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Complex(pub Complex64);
+
+unsafe impl Trace for Complex {
+    fn accept<V: dumpster::Visitor>(&self, _visitor: &mut V) -> std::result::Result<(), ()> {
+        Ok(())
+    }
+}
+
+impl Deref for Complex {
+    type Target = Complex64;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Display for Complex {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Complex64 { re, im } = self.0;
+        let sign = if im.is_sign_negative() { '-' } else { '+' };
+        write!(f, "{re:?}{sign}{:?}i", im.abs())
+    }
+}
+
+impl From<BigInt> for Value {
+    fn from(num: BigInt) -> Self {
+        if let Some(num) = num.to_i64() {
+            Value::Integer(num)
+        } else {
+            alloc::record();
+            Value::BigInteger(Gc::new(BigInteger(num)))
+        }
+    }
+}
+
+impl From<BigRational> for Value {
+    fn from(num: BigRational) -> Self {
+        if num.is_integer() {
+            num.to_integer().into()
+        } else {
+            alloc::record();
+            Value::Rational(Gc::new(Rational(num)))
+        }
+    }
+}
+
+impl From<Complex64> for Value {
+    fn from(num: Complex64) -> Self {
+        alloc::record();
+        Value::Complex(Gc::new(Complex(num)))
+    }
+}
+
+impl Value {
+    pub fn is_exact(&self) -> bool {
+        matches!(
+            self,
+            Value::Integer(_) | Value::BigInteger(_) | Value::Rational(_)
+        )
+    }
+
+    pub fn to_big_integer(&self) -> Option<BigInt> {
+        match self {
+            &Value::Integer(num) => Some(num.into()),
+            Value::BigInteger(num) => Some(num.0.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn to_rational(&self) -> Option<BigRational> {
+        match self {
+            Value::Rational(num) => Some(num.0.clone()),
+            _ => self.to_big_integer().map(BigRational::from_integer),
+        }
+    }
+
+    pub fn numerator(&self) -> Option<BigInt> {
+        self.to_rational().map(|num| num.numer().clone())
+    }
+
+    pub fn denominator(&self) -> Option<BigInt> {
+        self.to_rational().map(|num| num.denom().clone())
+    }
+
+    pub fn is_complex(&self) -> bool {
+        matches!(self, Value::Complex(_))
+    }
+
+    pub fn to_complex(&self) -> Option<Complex64> {
+        match self {
+            Value::Complex(num) => Some(num.0),
+            _ => self.to_f64().map(Complex64::from),
+        }
+    }
+
+    pub fn to_f64(&self) -> Option<f64> {
+        match self {
+            &Value::Integer(num) => num.to_f64(),
+            Value::BigInteger(num) => num.to_f64(),
+            Value::Rational(num) => num.to_f64(),
+            &Value::Number(num) => Some(num),
+            _ => None,
+        }
+    }
+}
+
+enum Real {
+    Exact(BigRational),
+    Float(f64),
+}
+
+impl Real {
+    fn cmp(&self, other: &Real) -> Ordering {
+        match (self, other) {
+            (Real::Exact(a), Real::Exact(b)) => a.cmp(b),
+            (Real::Float(a), Real::Float(b)) => a
+                .partial_cmp(b)
+                .unwrap_or_else(|| a.is_nan().cmp(&b.is_nan())),
+            (Real::Exact(a), &Real::Float(b)) => match BigRational::from_float(b) {
+                Some(b) => a.cmp(&b),
+                None if b == f64::INFINITY || b.is_nan() => Ordering::Less,
+                None => Ordering::Greater,
+            },
+            (Real::Float(_), Real::Exact(_)) => other.cmp(self).reverse(),
+        }
+    }
+}
+
+fn parts(value: &Value) -> (Real, Real, u8) {
+    let zero = || Real::Exact(BigRational::default());
+    match value {
+        &Value::Number(num) => (Real::Float(num), zero(), 1),
+        Value::Complex(num) => (Real::Float(num.re), Real::Float(num.im), 2),
+        _ => (
+            Real::Exact(value.to_rational().unwrap_or_default()),
+            zero(),
+            0,
+        ),
+    }
+}
+
+// Orders numbers by real part, then imaginary part, then exact < float < complex, so
+// that numerically equal values of different kinds stay distinct. Ties between floats
+// are broken bitwise, which separates -0.0 from 0.0 and sorts NaN after +inf.
+pub fn total_cmp(a: &Value, b: &Value) -> Ordering {
+    if let (Value::Integer(a), Value::Integer(b)) = (a, b) {
+        return a.cmp(b);
+    }
+
+    let (a_re, a_im, a_rank) = parts(a);
+    let (b_re, b_im, b_rank) = parts(b);
+    a_re.cmp(&b_re)
+        .then_with(|| a_im.cmp(&b_im))
+        .then(a_rank.cmp(&b_rank))
+        .then_with(|| match (a, b) {
+            (Value::Number(a), Value::Number(b)) => a.total_cmp(b),
+            (Value::Complex(a), Value::Complex(b)) => {
+                a.re.total_cmp(&b.re).then(a.im.total_cmp(&b.im))
+            }
+            _ => Ordering::Equal,
+        })
+}
+
+pub fn cmp_numbers(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        _ if a.is_complex() || b.is_complex() => {
+            (a.to_complex()? == b.to_complex()?).then_some(Ordering::Equal)
+        }
+        (Value::Integer(a), Value::Integer(b)) => Some(a.cmp(b)),
+        _ if a.is_exact() && b.is_exact() => Some(a.to_rational()?.cmp(&b.to_rational()?)),
+        _ => a.to_f64()?.partial_cmp(&b.to_f64()?),
+    }
+}
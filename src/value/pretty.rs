@@ -0,0 +1,481 @@
+use std::collections::HashMap;
+
+use dumpster::unsync::Gc;
+
+use crate::{
+    Symbol, Value,
+    value::{Cell, Compound},
+};
+
+// A document in the style of Wadler's "A prettier printer". `Line` is a space when its
+// enclosing group fits on the rest of the line and a newline plus indentation otherwise.
+enum Doc {
+    Text(String),
+    Line,
+    Concat(Vec<Doc>),
+    Nest(usize, Box<Doc>),
+    Group(Box<Doc>),
+}
+
+impl Doc {
+    fn text<S: Into<String>>(s: S) -> Doc {
+        Doc::Text(s.into())
+    }
+
+    fn nest(indent: usize, doc: Doc) -> Doc {
+        Doc::Nest(indent, Box::new(doc))
+    }
+
+    fn group(doc: Doc) -> Doc {
+        Doc::Group(Box::new(doc))
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+type Command<'a> = (usize, Mode, &'a Doc);
+
+fn fits(mut remaining: isize, first: Command, rest: &[Command]) -> bool {
+    let mut stack = vec![first];
+    let mut rest = rest.iter().rev();
+    while remaining >= 0 {
+        let Some((indent, mode, doc)) = stack.pop().or_else(|| rest.next().copied()) else {
+            return true;
+        };
+        match doc {
+            Doc::Text(s) => remaining -= width(s),
+            Doc::Line if mode == Mode::Break => return true,
+            Doc::Line => remaining -= 1,
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+            Doc::Nest(extra, doc) => stack.push((indent + extra, mode, doc)),
+            Doc::Group(doc) => stack.push((indent, mode, doc)),
+        }
+    }
+    false
+}
+
+fn width(s: &str) -> isize {
+    isize::try_from(s.chars().count()).unwrap_or(isize::MAX)
+}
+
+fn layout(doc: &Doc, line_width: usize) -> String {
+    let line_width = isize::try_from(line_width).unwrap_or(isize::MAX);
+    let mut out = String::new();
+    let mut column = 0;
+    let mut stack = vec![(0, Mode::Break, doc)];
+    while let Some((indent, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(s) => {
+                out.push_str(s);
+                column += width(s);
+            }
+            Doc::Line if mode == Mode::Flat => {
+                out.push(' ');
+                column += 1;
+            }
+            Doc::Line => {
+                out.push('\n');
+                out.extend(std::iter::repeat_n(' ', indent));
+                column = isize::try_from(indent).unwrap_or(isize::MAX);
+            }
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+            Doc::Nest(extra, doc) => stack.push((indent + extra, mode, doc)),
+            Doc::Group(doc) => {
+                let flat = (indent, Mode::Flat, &**doc);
+                if mode == Mode::Flat || fits(line_width - column, flat, &stack) {
+                    stack.push(flat);
+                } else {
+                    stack.push((indent, Mode::Break, doc));
+                }
+            }
+        }
+    }
+    out
+}
+
+// Lays out values to fit a line width, the way `Display` would print them on one line.
+//
+// A list headed by a symbol with an indentation rule keeps that many arguments next to the
+// head and indents the rest as a body, so `(fn [x] body)` breaks before `body`. `let` keeps
+// each name next to its value, aligning the pairs after the head, so `(let x 1 y 2 body)`
+// breaks between the pairs and before `body`. Other calls and compounds indent all their
+// arguments. Collections deeper than `max_depth` are
+// printed as `...`, as are elements past `max_length`; boxes are printed with their contents
+// unless they contain themselves.
+#[derive(Clone, Debug)]
+pub struct Pretty {
+    width: usize,
+    indent: usize,
+    max_depth: Option<usize>,
+    max_length: Option<usize>,
+    rules: HashMap<Symbol, Form>,
+}
+
+// How a list headed by a symbol lays out its arguments when it doesn't fit on one line.
+#[derive(Clone, Copy, Debug)]
+enum Form {
+    // The first `n` arguments stay on the head's line and the rest are indented as a body.
+    Args(usize),
+    // Name/value pairs aligned after the head, then an indented body, like `let`.
+    Bindings,
+}
+
+impl Default for Pretty {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pretty {
+    #[must_use]
+    pub fn new() -> Self {
+        let rules = [
+            ("fn", Form::Args(1)),
+            ("let", Form::Bindings),
+            ("letrec", Form::Args(1)),
+            ("letfn", Form::Args(1)),
+            ("loop", Form::Args(1)),
+            ("if", Form::Args(1)),
+            ("match", Form::Args(1)),
+            ("do", Form::Args(0)),
+            ("fresh", Form::Args(1)),
+            ("conde", Form::Args(0)),
+            ("run", Form::Args(2)),
+            ("run*", Form::Args(1)),
+        ];
+        Self {
+            width: 80,
+            indent: 2,
+            max_depth: None,
+            max_length: None,
+            rules: rules
+                .into_iter()
+                .map(|(head, args)| (Symbol::new(head), args))
+                .collect(),
+        }
+    }
+
+    // A single line with small limits, for embedding values in error messages.
+    #[must_use]
+    pub fn brief() -> Self {
+        Self::new()
+            .width(usize::MAX)
+            .max_depth(Some(4))
+            .max_length(Some(8))
+    }
+
+    #[must_use]
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    #[must_use]
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    #[must_use]
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    #[must_use]
+    pub fn max_length(mut self, max_length: Option<usize>) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    // Keeps the first `args` arguments of lists headed by `head` on the head's line.
+    #[must_use]
+    pub fn rule<S: AsRef<str>>(mut self, head: S, args: usize) -> Self {
+        self.rules.insert(Symbol::new(head), Form::Args(args));
+        self
+    }
+
+    // Lays out lists headed by `head` like `let`, as name/value pairs followed by a body.
+    #[must_use]
+    pub fn bindings_rule<S: AsRef<str>>(mut self, head: S) -> Self {
+        self.rules.insert(Symbol::new(head), Form::Bindings);
+        self
+    }
+
+    #[must_use]
+    pub fn render(&self, value: &Value) -> String {
+        let doc = Printer {
+            config: self,
+            boxes: Vec::new(),
+        }
+        .doc(value, 0);
+        layout(&doc, self.width)
+    }
+}
+
+struct Printer<'a> {
+    config: &'a Pretty,
+    boxes: Vec<Gc<Cell>>,
+}
+
+impl Printer<'_> {
+    fn doc(&mut self, value: &Value, depth: usize) -> Doc {
+        let collection = matches!(
+            value,
+            Value::Vector(_) | Value::Map(_) | Value::Set(_) | Value::Compound(_) | Value::Box(_)
+        );
+        if collection && self.config.max_depth.is_some_and(|max| depth >= max) {
+            return Doc::text("...");
+        }
+
+        match value {
+            Value::Vector(values) => {
+                let docs = self.docs(values.iter(), depth);
+                Self::sequence("[", docs, "]")
+            }
+            Value::Map(entries) => {
+                let entries = entries.sorted();
+                let docs = self.limit(entries.into_iter(), |printer, (key, value)| {
+                    let key = printer.doc(key, depth + 1);
+                    let value = printer.doc(value, depth + 1);
+                    Doc::group(Doc::Concat(vec![
+                        key,
+                        Doc::nest(printer.config.indent, Doc::Concat(vec![Doc::Line, value])),
+                    ]))
+                });
+                Self::sequence("{", docs, "}")
+            }
+            Value::Set(values) => {
+                let docs = self.docs(values.sorted().into_iter(), depth);
+                Self::sequence("#{", docs, "}")
+            }
+            Value::Compound(compound) => self.compound(compound, depth),
+            Value::Box(cell) => {
+                if self.boxes.iter().any(|seen| Gc::ptr_eq(seen, cell)) {
+                    return Doc::text("(#box ...)");
+                }
+                self.boxes.push(cell.clone());
+                let doc = self.doc(&cell.get(), depth + 1);
+                self.boxes.pop();
+                self.call(Doc::text("#box"), vec![doc], 0)
+            }
+            _ => Doc::text(value.to_string()),
+        }
+    }
+
+    fn compound(&mut self, compound: &Compound, depth: usize) -> Doc {
+        let prefix = if compound.is_quote() {
+            "'"
+        } else if compound.is_quasiquote() {
+            "`"
+        } else if compound.is_unquote() {
+            ","
+        } else if compound.is_unquote_splicing() {
+            ",@"
+        } else if compound.is_cons() {
+            return self.list(compound, depth);
+        } else {
+            let head = format!("#{}", compound.type_);
+            let args = self.docs(compound.values.iter(), depth);
+            return self.form(&head, args, self.rule(compound.type_));
+        };
+
+        let [value] = compound.as_array().unwrap();
+        Doc::Concat(vec![Doc::text(prefix), self.doc(value, depth)])
+    }
+
+    fn list(&mut self, compound: &Compound, depth: usize) -> Doc {
+        let [head, mut tail] = compound.as_array().unwrap().each_ref();
+        let mut values = vec![head];
+        while let Value::Compound(cons) = tail
+            && cons.is_cons()
+        {
+            let [head, next] = cons.as_array().unwrap();
+            values.push(head);
+            tail = next;
+        }
+
+        let mut docs = self.docs(values.iter().copied(), depth);
+        if !tail.is_nil() {
+            docs.push(Doc::text("."));
+            docs.push(self.doc(tail, depth + 1));
+        }
+
+        match values[0] {
+            Value::Symbol(head) => {
+                docs.remove(0);
+                self.form(head.as_str(), docs, self.rule(*head))
+            }
+            _ => Self::sequence("(", docs, ")"),
+        }
+    }
+
+    fn rule(&self, head: Symbol) -> Form {
+        self.config
+            .rules
+            .get(&head)
+            .copied()
+            .unwrap_or(Form::Args(0))
+    }
+
+    fn form(&self, head: &str, args: Vec<Doc>, form: Form) -> Doc {
+        match form {
+            Form::Args(distinguished) => self.call(Doc::text(head), args, distinguished),
+            Form::Bindings => self.bindings(head, args),
+        }
+    }
+
+    fn docs<'v>(&mut self, values: impl Iterator<Item = &'v Value>, depth: usize) -> Vec<Doc> {
+        self.limit(values, |printer, value| printer.doc(value, depth + 1))
+    }
+
+    fn limit<T>(
+        &mut self,
+        items: impl Iterator<Item = T>,
+        mut doc: impl FnMut(&mut Self, T) -> Doc,
+    ) -> Vec<Doc> {
+        let mut docs = Vec::new();
+        for (i, item) in items.enumerate() {
+            if self.config.max_length.is_some_and(|max| i >= max) {
+                docs.push(Doc::text("..."));
+                break;
+            }
+            docs.push(doc(self, item));
+        }
+        docs
+    }
+
+    // `(head a b c)`, with the first `distinguished` arguments kept on the head's line when
+    // the whole form doesn't fit.
+    fn call(&self, head: Doc, args: Vec<Doc>, distinguished: usize) -> Doc {
+        let mut line = vec![Doc::text("("), head];
+        let mut body = Vec::new();
+        for (i, arg) in args.into_iter().enumerate() {
+            if i < distinguished {
+                line.push(Doc::text(" "));
+                line.push(arg);
+            } else {
+                body.push(Doc::Line);
+                body.push(arg);
+            }
+        }
+        line.push(Doc::nest(self.config.indent, Doc::Concat(body)));
+        line.push(Doc::text(")"));
+        Doc::group(Doc::Concat(line))
+    }
+
+    // `(let x 1 y 2 body)`, with each name kept next to its value where it fits, the pairs
+    // aligned after the head, and the body indented.
+    fn bindings(&self, head: &str, mut args: Vec<Doc>) -> Doc {
+        let body = if args.len().is_multiple_of(2) {
+            None
+        } else {
+            args.pop()
+        };
+
+        let mut pairs = Vec::new();
+        let mut args = args.into_iter();
+        while let (Some(name), Some(value)) = (args.next(), args.next()) {
+            if !pairs.is_empty() {
+                pairs.push(Doc::Line);
+            }
+            pairs.push(Doc::group(Doc::Concat(vec![
+                name,
+                Doc::nest(self.config.indent, Doc::Concat(vec![Doc::Line, value])),
+            ])));
+        }
+
+        let mut line = vec![Doc::text("("), Doc::text(head)];
+        if !pairs.is_empty() {
+            line.push(Doc::text(" "));
+            line.push(Doc::nest(head.chars().count() + 2, Doc::Concat(pairs)));
+        }
+        if let Some(body) = body {
+            line.push(Doc::nest(
+                self.config.indent,
+                Doc::Concat(vec![Doc::Line, body]),
+            ));
+        }
+        line.push(Doc::text(")"));
+        Doc::group(Doc::Concat(line))
+    }
+
+    // Elements aligned one past the opening delimiter when they don't fit on one line.
+    fn sequence(open: &str, docs: Vec<Doc>, close: &str) -> Doc {
+        let mut inner = Vec::new();
+        for (i, doc) in docs.into_iter().enumerate() {
+            if i > 0 {
+                inner.push(Doc::Line);
+            }
+            inner.push(doc);
+        }
+        Doc::group(Doc::Concat(vec![
+            Doc::text(open),
+            Doc::nest(open.chars().count(), Doc::Concat(inner)),
+            Doc::text(close),
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Module, VM, Value};
+
+    use super::Pretty;
+
+    fn read(input: &str) -> Value {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+        module.eval_str(format!("'{input}")).unwrap()
+    }
+
+    #[test]
+    fn layout() {
+        let value = read("(let x (f 1 2) y (g x) (if (< x y) (h x y) (fn [a] (+ a x y))))");
+        assert_eq!(value.to_string(), Pretty::new().render(&value));
+        assert_eq!(
+            "(let x (f 1 2)\n     y (g x)\n  (if (< x y)\n    (h x y)\n    (fn [a] (+ a x y))))",
+            Pretty::new().width(30).render(&value)
+        );
+        assert_eq!(
+            "(let xs (list 1 2 3)\n  xs)",
+            Pretty::new()
+                .width(20)
+                .render(&read("(let xs (list 1 2 3) xs)"))
+        );
+        assert_eq!(
+            "(#add\n  x\n  (#mul 2 y))",
+            Pretty::new().width(14).render(&read("#add(x #mul(2 y))"))
+        );
+    }
+
+    #[test]
+    fn limits() {
+        let value = read("[1 [2 [3 [4]]] {:a (5 6 7 8)}]");
+        let pretty = Pretty::new().max_depth(Some(2)).max_length(Some(2));
+        assert_eq!("[1 [2 ...] ...]", pretty.render(&value));
+
+        let cell = Value::boxed(Value::nil());
+        let Value::Box(inner) = &cell else {
+            unreachable!();
+        };
+        inner.set(Value::vector([cell.clone(), 1.into()]));
+        assert_eq!("(#box [(#box ...) 1])", Pretty::new().render(&cell));
+        inner.set(Value::nil());
+    }
+
+    #[test]
+    fn errors() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+        let err = module.eval_str("($add 1 [1 2 3 4 5 6 7 8 9])").unwrap_err();
+        assert_eq!(
+            "expected number, got [1 2 3 4 5 6 7 8 ...]",
+            err.to_string()
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use std::ops::{Deref, DerefMut};
+
+use dumpster::{Trace, unsync::Gc};
+
+use crate::{Value, value::alloc};
+
+use super::lineage::Lineage;
+
+// Cloning a `Vector` shares its nodes, so a vector derived from another, as by `assoc` or
+// `conj`, is made by cloning the `Vector` rather than the `im::Vector` inside it.
+#[derive(Clone, Debug, Default)]
+pub struct Vector {
+    values: im::Vector<Value>,
+    lineage: Lineage,
+}
+
+impl Vector {
+    pub(crate) fn refresh(&self) {
+        self.lineage.refresh();
+    }
+}
+
+unsafe impl Trace for Vector {
+    fn accept<V: dumpster::Visitor>(&self, visitor: &mut V) -> std::result::Result<(), ()> {
+        if self.lineage.is_traced() {
+            for value in &self.values {
+                value.accept(visitor)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PartialEq for Vector {
+    fn eq(&self, other: &Self) -> bool {
+        self.values == other.values
+    }
+}
+
+impl Deref for Vector {
+    type Target = im::Vector<Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.values
+    }
+}
+
+impl DerefMut for Vector {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.values
+    }
+}
+
+// The values must be in nodes of their own; see `Vector`.
+impl From<im::Vector<Value>> for Vector {
+    fn from(values: im::Vector<Value>) -> Self {
+        Vector {
+            values,
+            lineage: Lineage::new(),
+        }
+    }
+}
+
+impl From<Vector> for Value {
+    fn from(vector: Vector) -> Self {
+        alloc::record();
+        Value::Vector(Gc::new(vector))
+    }
+}
+
+impl From<im::Vector<Value>> for Value {
+    fn from(values: im::Vector<Value>) -> Self {
+        Vector::from(values).into()
+    }
+}
+
+impl Value {
+    pub fn vector<T: AsRef<[Value]>>(values: T) -> Self {
+        values
+            .as_ref()
+            .iter()
+            .cloned()
+            .collect::<im::Vector<_>>()
+            .into()
+    }
+}
@@ -0,0 +1,160 @@
+use anyhow::anyhow;
+
+use crate::{Env, Expr, FnId, Inst, Result, Symbol, VM, Value};
+
+use super::Frame;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Status {
+    Suspended,
+    Finished(Value),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum State {
+    Running,
+    Finished(Value),
+    Aborted,
+}
+
+#[derive(Debug)]
+pub struct Execution<'a> {
+    vm: &'a mut VM,
+    fn_id: FnId,
+    frame_base: usize,
+    value_base: usize,
+    state: State,
+}
+
+impl<'a> Execution<'a> {
+    pub(super) fn new(vm: &'a mut VM, fn_id: FnId, frame_base: usize, value_base: usize) -> Self {
+        Execution {
+            vm,
+            fn_id,
+            frame_base,
+            value_base,
+            state: State::Running,
+        }
+    }
+
+    #[must_use]
+    pub fn vm(&self) -> &VM {
+        self.vm
+    }
+
+    #[must_use]
+    pub fn frames(&self) -> &[Frame] {
+        let frames = &self.vm.frames;
+        &frames[self.frame_base.min(frames.len())..]
+    }
+
+    #[must_use]
+    pub fn values(&self) -> &[Value] {
+        let values = &self.vm.values;
+        &values[self.value_base.min(values.len())..]
+    }
+
+    #[must_use]
+    pub fn current_frame(&self) -> Option<&Frame> {
+        self.frames().last()
+    }
+
+    #[must_use]
+    pub fn current_inst(&self) -> Option<&Inst> {
+        match self.current_frame()? {
+            Frame::Compiled(frame) => self.vm.inst_at(frame.fn_id, frame.pc),
+            Frame::Native(_) => None,
+        }
+    }
+
+    #[must_use]
+    pub fn frame_locals(&self, index: usize) -> Vec<(Symbol, Value)> {
+        let Some(Frame::Compiled(frame)) = self.frames().get(index) else {
+            return Vec::new();
+        };
+
+        let names = self.vm.local_names(frame.fn_id);
+        names
+            .iter()
+            .copied()
+            .zip(frame.locals.iter().cloned())
+            .collect()
+    }
+
+    pub fn eval_in_frame(&mut self, index: usize, env: &Env, expr: &Expr) -> Result<Value> {
+        if index >= self.frames().len() {
+            return Err(anyhow!("no frame at index {index}"));
+        }
+
+        let mut frame_env = env.clone();
+        for (name, value) in self.frame_locals(index) {
+            frame_env.insert(name, value);
+        }
+
+        let allocation_base = self.vm.allocation_base;
+        let result = self.vm.eval(&frame_env, expr);
+        self.vm.allocation_base = allocation_base;
+        result
+    }
+
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        !matches!(self.state, State::Running)
+    }
+
+    pub fn step(&mut self, count: usize) -> Result<Status> {
+        self.advance(Some(count))
+    }
+
+    pub fn resume(mut self) -> Result<Value> {
+        match self.advance(None)? {
+            Status::Finished(value) => Ok(value),
+            Status::Suspended => unreachable!(),
+        }
+    }
+
+    pub fn abort(self) {}
+
+    fn advance(&mut self, limit: Option<usize>) -> Result<Status> {
+        match &self.state {
+            State::Running => {}
+            State::Finished(value) => return Ok(Status::Finished(value.clone())),
+            State::Aborted => return Err(anyhow!("execution was aborted")),
+        }
+
+        let mut steps = 0;
+        while self.vm.frames.len() > self.frame_base {
+            if limit.is_some_and(|limit| steps >= limit) {
+                return Ok(Status::Suspended);
+            }
+
+            if let Err(err) = self.vm.run_once() {
+                self.cleanup();
+                self.state = State::Aborted;
+                return Err(err);
+            }
+
+            steps += 1;
+        }
+
+        self.vm.compiled_functions.remove(self.fn_id);
+        let value = self.vm.pop_value();
+        self.state = State::Finished(value.clone());
+        Ok(Status::Finished(value))
+    }
+
+    fn cleanup(&mut self) {
+        self.vm.frames.truncate(self.frame_base);
+        self.vm.values.truncate(self.value_base);
+        self.vm.sync_profiler();
+        self.vm.compiled_functions.remove(self.fn_id);
+    }
+}
+
+impl Drop for Execution<'_> {
+    fn drop(&mut self) {
+        if self.state == State::Running {
+            self.cleanup();
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{FnId, Value};
+use crate::{FnId, FnRef, Value};
 
 #[derive(Clone, Debug)]
 pub struct Compiled {
@@ -32,7 +32,7 @@ impl From<Native> for Frame {
 }
 
 impl Frame {
-    pub fn compiled(fn_id: FnId, locals: Vec<Value>) -> Self {
+    pub(crate) fn compiled(fn_id: FnId, locals: Vec<Value>) -> Self {
         Frame::Compiled(Compiled {
             fn_id,
             locals,
@@ -40,17 +40,43 @@ impl Frame {
         })
     }
 
-    pub fn native(fn_id: FnId, locals: Vec<Value>) -> Self {
+    pub(crate) fn native(fn_id: FnId, locals: Vec<Value>) -> Self {
         Frame::Native(Native { fn_id, locals })
     }
 
+    #[must_use]
+    pub fn fn_id(&self) -> FnId {
+        match self {
+            Frame::Compiled(compiled_frame) => compiled_frame.fn_id,
+            Frame::Native(native_frame) => native_frame.fn_id,
+        }
+    }
+
+    #[must_use]
+    pub fn fn_ref(&self) -> FnRef {
+        match self {
+            Frame::Compiled(compiled_frame) => FnRef::Compiled(compiled_frame.fn_id),
+            Frame::Native(native_frame) => FnRef::Native(native_frame.fn_id),
+        }
+    }
+
+    #[must_use]
+    pub fn pc(&self) -> Option<u32> {
+        match self {
+            Frame::Compiled(compiled_frame) => Some(compiled_frame.pc),
+            Frame::Native(_) => None,
+        }
+    }
+
+    #[must_use]
     pub fn locals(&self) -> &[Value] {
         match self {
             Frame::Compiled(compiled_frame) => &compiled_frame.locals,
             Frame::Native(native_frame) => &native_frame.locals,
         }
     }
-    pub fn locals_mut(&mut self) -> &mut Vec<Value> {
+
+    pub(crate) fn locals_mut(&mut self) -> &mut Vec<Value> {
         match self {
             Frame::Compiled(compiled_frame) => &mut compiled_frame.locals,
             Frame::Native(native_frame) => &mut native_frame.locals,
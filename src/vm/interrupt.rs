@@ -0,0 +1,41 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::anyhow;
+
+use crate::Result;
+
+#[derive(Clone, Debug, Default)]
+pub struct Interrupt {
+    flag: Arc<AtomicBool>,
+}
+
+impl Interrupt {
+    #[must_use]
+    pub fn new() -> Self {
+        Interrupt::default()
+    }
+
+    pub fn trigger(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn is_triggered(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+    }
+
+    pub fn reset(&self) {
+        self.flag.store(false, Ordering::Relaxed);
+    }
+
+    pub fn check(&self) -> Result<()> {
+        if self.flag.swap(false, Ordering::Relaxed) {
+            Err(anyhow!("interrupted"))
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use anyhow::anyhow;
+
+use crate::Result;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Limits {
+    pub max_frames: Option<usize>,
+    pub max_values: Option<usize>,
+    // The number of heap objects (`Gc` cells, bignums, collections) an evaluation may create.
+    // Each counts once whatever its size, so this bounds object churn, not bytes.
+    pub max_allocations: Option<usize>,
+}
+
+impl Limits {
+    #[must_use]
+    pub fn unlimited() -> Self {
+        Limits::default()
+    }
+
+    #[must_use]
+    pub fn max_frames(self, max_frames: usize) -> Self {
+        Limits {
+            max_frames: Some(max_frames),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn max_values(self, max_values: usize) -> Self {
+        Limits {
+            max_values: Some(max_values),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn max_allocations(self, max_allocations: usize) -> Self {
+        Limits {
+            max_allocations: Some(max_allocations),
+            ..self
+        }
+    }
+
+    pub fn check(&self, frame_count: usize, value_count: usize, allocations: usize) -> Result<()> {
+        if let Some(max_frames) = self.max_frames
+            && frame_count > max_frames
+        {
+            return Err(anyhow!("call depth limit of {max_frames} exceeded"));
+        }
+
+        if let Some(max_values) = self.max_values
+            && value_count > max_values
+        {
+            return Err(anyhow!("stack depth limit of {max_values} exceeded"));
+        }
+
+        if let Some(max_allocations) = self.max_allocations
+            && allocations > max_allocations
+        {
+            return Err(anyhow!("allocation limit of {max_allocations} exceeded"));
+        }
+
+        Ok(())
+    }
+}
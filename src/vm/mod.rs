@@ -1,15 +1,29 @@
+mod execution;
 mod frame;
+mod interrupt;
+mod limits;
+mod profile;
 mod step;
+mod trace;
+
+use std::time::Instant;
 
 use anyhow::anyhow;
+pub use execution::{Execution, Status};
 pub use frame::Frame;
+pub use interrupt::Interrupt;
+pub use limits::Limits;
+pub use profile::{FnProfile, Profiler};
+pub use trace::{Tracer, WriteTracer};
 
 use intmap::IntMap;
 
 use crate::{
-    Arity, Env, Expr, FnId, Inst, Result, ResultIterator, Value,
+    Arity, Env, Expr, FnId, FnRef, Inst, Pretty, Result, ResultIterator, Symbol, Value,
     compiler::{Compiler, context::Context},
     function::{self, RawFn},
+    symbol,
+    value::alloc,
 };
 
 #[derive(Debug)]
@@ -20,6 +34,12 @@ pub struct VM {
     next_compiled_fn_id: FnId,
     native_functions: IntMap<FnId, function::Native>,
     next_native_fn_id: FnId,
+    limits: Limits,
+    allocation_base: usize,
+    interrupt: Interrupt,
+    tracer: trace::Slot,
+    profiler: Option<Profiler>,
+    warnings: Vec<String>,
 }
 
 impl VM {
@@ -33,19 +53,140 @@ impl VM {
             next_compiled_fn_id: 0,
             native_functions: IntMap::new(),
             next_native_fn_id: 0,
+            limits: Limits::unlimited(),
+            allocation_base: 0,
+            interrupt: Interrupt::new(),
+            tracer: trace::Slot(None),
+            profiler: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_limits(limits: Limits) -> Self {
+        VM {
+            limits,
+            ..VM::new()
+        }
+    }
+
+    #[must_use]
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn set_tracer(&mut self, tracer: Box<dyn Tracer>) {
+        self.tracer.0 = Some(tracer);
+    }
+
+    pub fn take_tracer(&mut self) -> Option<Box<dyn Tracer>> {
+        self.tracer.0.take()
+    }
+
+    pub fn set_profiler(&mut self, profiler: Profiler) {
+        self.profiler = Some(profiler);
+    }
+
+    #[must_use]
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+
+    pub fn take_profiler(&mut self) -> Option<Profiler> {
+        self.profiler.take()
+    }
+
+    // Compile-time warnings, such as unreachable `match` clauses, since the last call.
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    pub(crate) fn warn(&mut self, warning: String) {
+        self.warnings.push(warning);
+    }
+
+    #[must_use]
+    pub fn interrupt_handle(&self) -> Interrupt {
+        self.interrupt.clone()
+    }
+
+    #[must_use]
+    pub fn inst_at(&self, fn_id: FnId, pc: u32) -> Option<&Inst> {
+        let func = self.compiled_functions.get(fn_id)?;
+        func.code.get(pc as usize)
+    }
+
+    pub fn name_function(&mut self, fn_ref: FnRef, name: Symbol) {
+        match fn_ref {
+            FnRef::Compiled(fn_id) => {
+                if let Some(func) = self.compiled_functions.get_mut(fn_id) {
+                    func.name = Some(name);
+                }
+            }
+            FnRef::Native(fn_id) => {
+                if let Some(func) = self.native_functions.get_mut(fn_id) {
+                    func.name = Some(name);
+                }
+            }
         }
     }
 
+    #[must_use]
+    pub fn fn_name(&self, fn_ref: FnRef) -> String {
+        let name = match fn_ref {
+            FnRef::Compiled(fn_id) => self.compiled_functions.get(fn_id).and_then(|f| f.name),
+            FnRef::Native(fn_id) => self.native_functions.get(fn_id).and_then(|f| f.name),
+        };
+
+        name.map_or_else(|| fn_ref.to_string(), |name| name.to_string())
+    }
+
+    #[must_use]
+    pub fn local_names(&self, fn_id: FnId) -> &[Symbol] {
+        self.compiled_functions
+            .get(fn_id)
+            .map_or(&[], |func| &func.local_names)
+    }
+
+    // Source lines whose code starts at `pc`.
+    pub fn lines_at(&self, fn_id: FnId, pc: u32) -> impl Iterator<Item = u32> + '_ {
+        self.compiled_functions
+            .get(fn_id)
+            .map_or(&[][..], |func| &func.lines)
+            .iter()
+            .filter(move |&&(start, _)| start == pc)
+            .map(|&(_, line)| line)
+    }
+
+    // The line of the innermost form started at or before `pc`, if the code came from a source.
+    #[must_use]
+    pub fn line_at(&self, fn_id: FnId, pc: u32) -> Option<u32> {
+        let lines = &self.compiled_functions.get(fn_id)?.lines;
+        let end = lines.partition_point(|&(start, _)| start <= pc);
+        lines[..end].last().map(|&(_, line)| line)
+    }
+
     fn relative_frame(&mut self, frame_index: u16) -> &mut Frame {
         let max_index = self.frames.len() - 1;
         let i = max_index - frame_index as usize - 1;
         &mut self.frames[i]
     }
 
-    pub fn register_closure<A: Into<Arity>>(&mut self, arity: A, code: Vec<Inst>) -> FnId {
+    pub fn register_closure<A: Into<Arity>>(
+        &mut self,
+        arity: A,
+        code: Vec<Inst>,
+        local_names: Vec<Symbol>,
+        lines: Vec<(u32, u32)>,
+    ) -> FnId {
         let id = self.next_compiled_fn_id;
         self.next_compiled_fn_id += 1;
-        let compiled_function = function::Compiled::new(id, arity, code);
+        let mut compiled_function = function::Compiled::new(id, arity, code, local_names);
+        compiled_function.lines = lines;
         self.compiled_functions.insert(id, compiled_function);
         id
     }
@@ -69,6 +210,27 @@ impl VM {
     }
 
     pub fn eval(&mut self, env: &Env, expr: &Expr) -> Result<Value> {
+        self.start(env, expr)?.resume()
+    }
+
+    // Evaluates built-in definitions, such as the logic prelude, without tracing, profiling or
+    // counting them against the limits.
+    pub(crate) fn eval_builtin(&mut self, env: &Env, expr: &Expr) -> Result<Value> {
+        let tracer = self.tracer.0.take();
+        let profiler = self.profiler.take();
+        let limits = std::mem::replace(&mut self.limits, Limits::unlimited());
+        let result = self.eval(env, expr);
+        self.tracer.0 = tracer;
+        self.profiler = profiler;
+        self.limits = limits;
+        result
+    }
+
+    pub fn start(&mut self, env: &Env, expr: &Expr) -> Result<Execution<'_>> {
+        if let Some(var) = expr.assigned_vars().into_iter().next() {
+            return Err(anyhow!("can't assign to global `{var}`"));
+        }
+
         let free_vars = expr.free_vars();
 
         let mut context = Context::blank();
@@ -81,36 +243,93 @@ impl VM {
         context = compiler.compile(context, expr)?;
         context.code.emit(Inst::Return);
 
+        let local_values = free_vars.iter().map(|&var| env.get(var)).try_collect()?;
+
         let code = context.code.extract();
+        let local_names = context.locals.vars().to_vec();
         let fn_id = self.next_compiled_fn_id;
-        let function = function::Compiled::new(fn_id, 0, code);
+        self.next_compiled_fn_id += 1;
+        let mut function = function::Compiled::new(fn_id, 0, code, local_names);
+        function.lines = context.lines;
+        function.name = Some(*symbol::EVAL);
         self.compiled_functions.insert(fn_id, function);
 
-        let local_values = free_vars.iter().map(|&var| env.get(var)).try_collect()?;
         let frame = Frame::compiled(fn_id, local_values);
+        let frame_base = self.frames.len();
+        let value_base = self.values.len();
         self.frames.push(frame);
+        self.allocation_base = alloc::count();
 
-        let run_result = self.run();
-        self.compiled_functions.remove(fn_id);
-        run_result?;
+        Ok(Execution::new(self, fn_id, frame_base, value_base))
+    }
 
-        let value = self.pop_value();
-        Ok(value)
+    fn check_limits(&self) -> Result<()> {
+        let allocations = alloc::count().wrapping_sub(self.allocation_base);
+        self.limits
+            .check(self.frames.len(), self.values.len(), allocations)
     }
 
-    fn run(&mut self) -> Result<()> {
-        while let Some(frame) = self.frames.pop() {
-            match frame {
-                Frame::Compiled(compiled_frame) => {
-                    if let Some(frame) = self.step(compiled_frame)? {
-                        self.frames.push(frame);
-                    }
+    fn run_once(&mut self) -> Result<()> {
+        let result = if self.profiler.is_some() {
+            self.profiled_run_once()
+        } else {
+            self.try_run_once()
+        };
+
+        if let (Err(err), Some(tracer)) = (&result, &mut self.tracer.0) {
+            tracer.error(err, self.frames.len() + 1);
+        }
+
+        result
+    }
+
+    fn profiled_run_once(&mut self) -> Result<()> {
+        let mut profiler = self.profiler.take().unwrap();
+        profiler.sync(&self.frames, |fn_ref| self.fn_name(fn_ref));
+        let fn_ref = self.frames.last().map(Frame::fn_ref);
+
+        let start = Instant::now();
+        let result = self.try_run_once();
+        let elapsed = start.elapsed();
+
+        if let Some(fn_ref) = fn_ref {
+            profiler.record_step(fn_ref, elapsed);
+        }
+
+        profiler.sync(&self.frames, |fn_ref| self.fn_name(fn_ref));
+        self.profiler = Some(profiler);
+        result
+    }
+
+    // Closes the profiler's entries for frames that were unwound without returning.
+    fn sync_profiler(&mut self) {
+        if let Some(mut profiler) = self.profiler.take() {
+            profiler.sync(&self.frames, |fn_ref| self.fn_name(fn_ref));
+            self.profiler = Some(profiler);
+        }
+    }
+
+    fn try_run_once(&mut self) -> Result<()> {
+        self.check_limits()?;
+        let Some(frame) = self.frames.pop() else {
+            return Err(anyhow!("no frame to run"));
+        };
+
+        match frame {
+            Frame::Compiled(compiled_frame) => {
+                if let Some(frame) = self.step(compiled_frame)? {
+                    self.frames.push(frame);
                 }
-                Frame::Native(native_frame) => {
-                    let func = self.native_functions.get(native_frame.fn_id).unwrap();
-                    let value = func.apply(&native_frame.locals)?;
-                    self.values.push(value);
+            }
+            Frame::Native(native_frame) => {
+                if let Some(tracer) = &mut self.tracer.0 {
+                    let depth = self.frames.len() + 1;
+                    tracer.native(native_frame.fn_id, &native_frame.locals, depth);
                 }
+
+                let func = self.native_functions.get(native_frame.fn_id).unwrap();
+                let value = func.apply(&native_frame.locals)?;
+                self.values.push(value);
             }
         }
 
@@ -121,13 +340,13 @@ impl VM {
         match func {
             Value::Closure(closure) => Ok(self.compiled_frame(closure, arity)),
             &Value::NativeFunction(fn_id) => Ok(self.native_frame(fn_id, arity)),
-            _ => Err(anyhow!("can't call {func}")),
+            _ => Err(anyhow!("can't call {}", Pretty::brief().render(func))),
         }
     }
 
     fn compiled_frame(&mut self, closure: &function::Closure, arity: u16) -> Frame {
         let mut locals = Vec::new();
-        locals.extend(closure.values.clone());
+        locals.extend(closure.values().iter().cloned());
         locals.extend(self.pop_values(arity.into()));
         Frame::compiled(closure.fn_id, locals)
     }
@@ -144,3 +363,238 @@ impl Default for VM {
         VM::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use crate::{Inst, Limits, Module, Status, VM, Value, parser, symbol};
+
+    #[test]
+    fn frame_limit() {
+        let mut vm = VM::with_limits(Limits::unlimited().max_frames(64));
+        let mut module = Module::new(&mut vm);
+
+        let err = module
+            .eval_str("(let f (fn [f n] (f f n)) (f f 0))")
+            .unwrap_err();
+        assert_eq!("call depth limit of 64 exceeded", err.to_string());
+        assert_eq!(Value::from(3), module.eval_str("($add 1 2)").unwrap());
+    }
+
+    #[test]
+    fn value_limit() {
+        let mut vm = VM::with_limits(Limits::unlimited().max_values(2));
+        let mut module = Module::new(&mut vm);
+
+        let err = module.eval_str("(list 1 2 3)").unwrap_err();
+        assert_eq!("stack depth limit of 2 exceeded", err.to_string());
+        assert_eq!(Value::from(3), module.eval_str("($add 1 2)").unwrap());
+    }
+
+    #[test]
+    fn allocation_limit() {
+        let mut vm = VM::with_limits(Limits::unlimited().max_allocations(100));
+        let mut module = Module::new(&mut vm);
+
+        let input = "(loop [n 1000 l nil] (if ($eq n 0) l (recur ($sub n 1) (list l))))";
+        let err = module.eval_str(input).unwrap_err();
+        assert_eq!("allocation limit of 100 exceeded", err.to_string());
+        assert!(module.eval_str("(list 1 2 3)").is_ok());
+    }
+
+    #[test]
+    fn interrupt() {
+        let mut vm = VM::new();
+        let interrupt = vm.interrupt_handle();
+        let mut module = Module::new(&mut vm);
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            interrupt.trigger();
+        });
+
+        let err = module.eval_str("(loop [n 0] (recur n))").unwrap_err();
+        assert_eq!("interrupted", err.to_string());
+        handle.join().unwrap();
+
+        assert_eq!(Value::from(3), module.eval_str("($add 1 2)").unwrap());
+    }
+
+    #[test]
+    fn step() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+
+        let value = parser::parse("($add 1 2)", parser::value()).unwrap();
+        let mut execution = module.start(&value).unwrap();
+        assert_eq!(Some(&Inst::Value(Value::from(1))), execution.current_inst());
+
+        assert_eq!(Status::Suspended, execution.step(2).unwrap());
+        assert_eq!(&[Value::from(1), Value::from(2)], execution.values());
+        assert_eq!(1, execution.frames().len());
+        assert_eq!(Some(2), execution.frames()[0].pc());
+
+        assert_eq!(Value::from(3), execution.resume().unwrap());
+    }
+
+    #[test]
+    fn abort() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+
+        let value = parser::parse("(loop [n 0] (recur n))", parser::value()).unwrap();
+        let mut execution = module.start(&value).unwrap();
+        assert_eq!(Status::Suspended, execution.step(100).unwrap());
+        execution.abort();
+
+        assert_eq!(Value::from(3), module.eval_str("($add 1 2)").unwrap());
+    }
+
+    #[test]
+    fn assignment() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+        let mut eval = |input| module.eval_str(input).unwrap().to_string();
+
+        assert_eq!("5", eval("(let x 1 (do (set! x 5) x))"));
+        assert_eq!(
+            "[1 2 1]",
+            eval(
+                "(let make (fn [] (let n 0 (fn [] (set! n ($add n 1))))) a (make) b (make) [(a) (a) (b)])"
+            )
+        );
+        assert_eq!(
+            "7",
+            eval("((fn [x] (let g (fn [] (set! x ($add x 1))) (do (g) (g) x))) 5)")
+        );
+        assert_eq!(
+            "[0 10 20 200]",
+            eval(
+                "(let fs (loop [i 0 acc []] (if ($lt i 3) (recur ($add i 1) (conj acc (fn [] (do (set! i ($mul i 10)) i)))) acc)) [((nth fs 0)) ((nth fs 1)) ((nth fs 2)) ((nth fs 2))])"
+            )
+        );
+        assert_eq!("1", eval("((fn [x] 1) 2)"));
+
+        let err = module.eval_str("(set! x 1)").unwrap_err();
+        assert_eq!("can't assign to global `x`", err.to_string());
+    }
+
+    #[test]
+    fn letrec() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+        let mut eval = |input| module.eval_str(input).unwrap().to_string();
+
+        assert_eq!(
+            "3628800",
+            eval("(letrec [fact (fn [n] (if ($eq n 0) 1 ($mul n (fact ($sub n 1)))))] (fact 10))")
+        );
+        assert_eq!(
+            "[true true false]",
+            eval(
+                "(letfn [(even? [n] (if ($eq n 0) true (odd? ($sub n 1)))) (odd? [n] (if ($eq n 0) false (even? ($sub n 1))))] [(even? 10) (odd? 7) (even? 3)])"
+            )
+        );
+        assert_eq!(
+            "3",
+            eval(
+                "(let k 3 (letrec [f (fn [n] (if ($eq n 0) k (g ($sub n 1)))) g (fn [n] ((fn [] (f n))))] (f 5)))"
+            )
+        );
+
+        let err = module.eval_str("(letrec [f 1] f)").unwrap_err();
+        assert_eq!("`letrec` binding `f` must be a function", err.to_string());
+        let err = module.eval_str("(letrec 1 2)").unwrap_err();
+        assert_eq!("malformed `letrec` expression", err.to_string());
+        let err = module.eval_str("(letfn 1 2)").unwrap_err();
+        assert_eq!("malformed `letfn` expression", err.to_string());
+        let err = module.eval_str("(letfn [1] 2)").unwrap_err();
+        assert_eq!("malformed `letfn` expression", err.to_string());
+    }
+
+    #[test]
+    fn destructuring() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+        let mut eval = |input| module.eval_str(input).unwrap().to_string();
+
+        assert_eq!(
+            "[1 2 (3 4)]",
+            eval("(let (a b . rest) '(1 2 3 4) [a b rest])")
+        );
+        assert_eq!(
+            "(1 2 [3 4])",
+            eval("(let [x y & more] [1 2 3 4] (list x y more))")
+        );
+        assert_eq!("7", eval("(let (#point x y) #point(3 4) ($add x y))"));
+        assert_eq!("[1 2]", eval("(let {:x x :y y} {:x 1 :y 2 :z 3} [x y])"));
+        assert_eq!("[1 2 3]", eval("((fn [(a b) [c _]] [a b c]) '(1 2) [3 4])"));
+        assert_eq!(
+            "6",
+            eval(
+                "(loop [(a . rest) '(1 2 3) acc 0] (if ($eq rest nil) ($add acc a) (recur rest ($add acc a))))"
+            )
+        );
+
+        let err = module.eval_str("(let (a b) '(1 2 3) a)").unwrap_err();
+        assert_eq!(
+            "can't destructure (1 2 3) with pattern (a b)",
+            err.to_string()
+        );
+        let err = module.eval_str("(let {:x x} {:y 1} x)").unwrap_err();
+        assert_eq!(
+            "can't destructure {:y 1} with pattern {:x x}",
+            err.to_string()
+        );
+
+        // The reader rejects a quote without an argument, but values built in Rust may not.
+        let empty_quote = Value::compound(*symbol::QUOTE, Vec::new());
+        let let_ = Value::list([
+            Value::symbol("let"),
+            empty_quote.clone(),
+            1.into(),
+            2.into(),
+        ]);
+        assert!(module.eval(&let_).is_err());
+        let match_ = Value::list([Value::symbol("match"), 1.into(), empty_quote, 1.into()]);
+        assert!(module.eval(&match_).is_err());
+    }
+
+    #[test]
+    fn match_() {
+        let mut vm = VM::new();
+        let mut module = Module::new(&mut vm);
+        let mut eval = |input: &str| module.eval_str(input).unwrap().to_string();
+
+        let simplify = "(fn [e] (match e (#add 0 x) x (#add x 0) x (#mul (:or 0 0.0) _) 0 _ e))";
+        assert_eq!(
+            "[y y 0 (#pow y 2)]",
+            eval(&format!(
+                "(let s {simplify} [(s '#add(0 y)) (s '#add(y 0)) (s '#mul(0.0 y)) (s '#pow(y 2))])"
+            ))
+        );
+        assert_eq!(
+            "[1 2 (3)]",
+            eval("(match '(1 2 3) (a) a (a b . r) [a b r])")
+        );
+        assert_eq!("small", eval("(match 3 n :when ($lt n 5) 'small n 'big)"));
+        assert_eq!("-4", eval("(match #neg(#num(4)) (#neg (#num n)) ($neg n))"));
+        assert_eq!("2", eval("(match 'foo 'bar 1 'foo 2)"));
+        assert!(module.vm.take_warnings().is_empty());
+
+        assert_eq!(
+            Value::from(1),
+            module.eval_str("(match 1 x x 2 'two)").unwrap()
+        );
+        assert_eq!(
+            vec!["unreachable `match` clause 2 is covered by x".to_string()],
+            module.vm.take_warnings()
+        );
+
+        let err = module.eval_str("(match 9 1 'one)").unwrap_err();
+        assert_eq!("no `match` clause matches 9", err.to_string());
+        let err = module.eval_str("(match '(1 2) (x x) x)").unwrap_err();
+        assert_eq!("`x` is bound twice in pattern (x x)", err.to_string());
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use crate::{FnRef, Frame};
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct FnProfile {
+    pub name: String,
+    pub calls: u64,
+    pub exclusive_steps: u64,
+    pub inclusive_steps: u64,
+    pub exclusive_time: Duration,
+    pub inclusive_time: Duration,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    fn_ref: FnRef,
+    enter_time: Instant,
+    enter_steps: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Profiler {
+    sample_interval: u64,
+    steps: u64,
+    functions: HashMap<FnRef, FnProfile>,
+    stack: Vec<Entry>,
+    samples: BTreeMap<String, u64>,
+}
+
+impl Profiler {
+    #[must_use]
+    pub fn new() -> Self {
+        Profiler::with_sample_interval(1)
+    }
+
+    #[must_use]
+    pub fn with_sample_interval(sample_interval: u64) -> Self {
+        Profiler {
+            sample_interval: sample_interval.max(1),
+            steps: 0,
+            functions: HashMap::new(),
+            stack: Vec::new(),
+            samples: BTreeMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    #[must_use]
+    pub fn get(&self, fn_ref: FnRef) -> Option<&FnProfile> {
+        self.functions.get(&fn_ref)
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = (FnRef, &FnProfile)> {
+        self.functions
+            .iter()
+            .map(|(&fn_ref, profile)| (fn_ref, profile))
+    }
+
+    #[must_use]
+    pub fn folded(&self) -> String {
+        let mut output = String::new();
+        for (stack, count) in &self.samples {
+            writeln!(output, "{stack} {count}").unwrap();
+        }
+
+        output
+    }
+
+    pub(super) fn sync<F: Fn(FnRef) -> String>(&mut self, frames: &[Frame], name: F) {
+        let now = Instant::now();
+
+        while !self.stack.is_empty() {
+            let i = self.stack.len() - 1;
+            if i < frames.len() && self.stack[i].fn_ref == frames[i].fn_ref() {
+                break;
+            }
+
+            let entry = self.stack.pop().unwrap();
+            if self.stack.iter().any(|other| other.fn_ref == entry.fn_ref) {
+                continue;
+            }
+
+            let profile = self.functions.get_mut(&entry.fn_ref).unwrap();
+            profile.inclusive_time += now - entry.enter_time;
+            profile.inclusive_steps += self.steps - entry.enter_steps;
+        }
+
+        for frame in &frames[self.stack.len()..] {
+            let fn_ref = frame.fn_ref();
+            let profile = self.functions.entry(fn_ref).or_insert_with(|| FnProfile {
+                name: name(fn_ref),
+                ..FnProfile::default()
+            });
+            profile.calls += 1;
+
+            self.stack.push(Entry {
+                fn_ref,
+                enter_time: now,
+                enter_steps: self.steps,
+            });
+        }
+    }
+
+    pub(super) fn record_step(&mut self, fn_ref: FnRef, elapsed: Duration) {
+        self.steps += 1;
+
+        if let Some(profile) = self.functions.get_mut(&fn_ref) {
+            profile.exclusive_steps += 1;
+            profile.exclusive_time += elapsed;
+        }
+
+        if self.steps.is_multiple_of(self.sample_interval) {
+            let stack = self
+                .stack
+                .iter()
+                .map(|entry| self.functions[&entry.fn_ref].name.as_str())
+                .collect::<Vec<_>>()
+                .join(";");
+            *self.samples.entry(stack).or_insert(0) += 1;
+        }
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Module, Profiler, VM};
+
+    #[test]
+    fn profile() {
+        let mut vm = VM::new();
+        vm.set_profiler(Profiler::new());
+        let mut module = Module::new(&mut vm);
+
+        let input = "(let sq (fn [x] ($mul x x)) (list (sq 1) (sq 2)))";
+        module.eval_str(input).unwrap();
+
+        let profiler = vm.take_profiler().unwrap();
+        let profile = |name: &str| {
+            profiler
+                .functions()
+                .find(|(_, profile)| profile.name == name)
+                .map(|(_, profile)| profile.clone())
+                .unwrap()
+        };
+
+        let sq = profile("sq");
+        assert_eq!(2, sq.calls);
+        assert_eq!(8, sq.exclusive_steps);
+        assert_eq!(8, sq.inclusive_steps);
+
+        let list = profile("list");
+        assert_eq!(1, list.calls);
+        assert_eq!(1, list.exclusive_steps);
+
+        let eval = profile("eval");
+        assert_eq!(1, eval.calls);
+        assert_eq!(profiler.steps(), eval.inclusive_steps);
+        assert_eq!(profiler.steps() - 9, eval.exclusive_steps);
+        assert!(eval.inclusive_time >= sq.inclusive_time);
+
+        let folded = profiler.folded();
+        assert!(folded.lines().any(|line| line == "eval;sq 8"));
+        assert!(folded.lines().any(|line| line == "eval;list 1"));
+    }
+
+    #[test]
+    fn failed_eval() {
+        let mut vm = VM::new();
+        vm.set_profiler(Profiler::new());
+        let mut module = Module::new(&mut vm);
+
+        let input = "(let f (fn [x] ($add x nil)) (f 1))";
+        assert!(module.eval_str(input).is_err());
+        assert!(module.vm.profiler().unwrap().stack.is_empty());
+
+        module.eval_str("(list 1 2)").unwrap();
+        let profiler = vm.take_profiler().unwrap();
+        assert!(profiler.stack.is_empty());
+
+        let f = profiler
+            .functions()
+            .find(|(_, profile)| profile.name == "f")
+            .map(|(_, profile)| profile.clone())
+            .unwrap();
+        assert_eq!(1, f.calls);
+        assert_eq!(f.exclusive_steps, f.inclusive_steps);
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::anyhow;
 
-use crate::{Inst, Result, VM, Value};
+use crate::{Inst, Pretty, Result, VM, Value};
 
 use super::{Frame, frame};
 
@@ -9,6 +9,13 @@ impl VM {
     pub(crate) fn step(&mut self, mut current_frame: frame::Compiled) -> Result<Option<Frame>> {
         let func = self.compiled_functions.get(current_frame.fn_id).unwrap();
         let inst = &func.code[current_frame.pc as usize];
+
+        if let Some(tracer) = &mut self.tracer.0 {
+            let depth = self.frames.len() + 1;
+            let stack = self.values.len();
+            tracer.inst(current_frame.fn_id, current_frame.pc, inst, depth, stack);
+        }
+
         current_frame.pc += 1;
 
         match inst {
@@ -24,6 +31,25 @@ impl VM {
                 let value = Value::list(values);
                 self.values.push(value);
             }
+            &Inst::Vector(value_count) => {
+                let values = self.pop_values(value_count.into());
+                let value = Value::vector(values);
+                self.values.push(value);
+            }
+            &Inst::HashMap(entry_count) => {
+                let values = self.pop_values(usize::from(entry_count) * 2);
+                let entries: Vec<_> = values
+                    .chunks_exact(2)
+                    .map(|pair| (pair[0].clone(), pair[1].clone()))
+                    .collect();
+                let value = Value::map(entries);
+                self.values.push(value);
+            }
+            &Inst::HashSet(value_count) => {
+                let values = self.pop_values(value_count.into());
+                let value = Value::set(values);
+                self.values.push(value);
+            }
             &Inst::Compound(type_, value_count) => {
                 let values = self.pop_values(value_count.into());
                 let value = Value::compound(type_, values);
@@ -34,6 +60,63 @@ impl VM {
                 let value = Value::closure(fn_id, values);
                 self.values.push(value);
             }
+            Inst::Box => {
+                let value = self.pop_value();
+                self.values.push(Value::boxed(value));
+            }
+            Inst::Unbox => {
+                let value = self.pop_value().as_box()?.get();
+                self.values.push(value);
+            }
+            Inst::SetBox => {
+                let value = self.pop_value();
+                self.pop_value().as_box()?.set(value.clone());
+                self.values.push(value);
+            }
+            &Inst::Patch(index) => {
+                let value = self.pop_value();
+                let Value::Closure(closure) = self.pop_value() else {
+                    return Err(anyhow!("can't patch a non-closure"));
+                };
+                closure.patch(index.into(), value);
+            }
+            Inst::Is(shape) => {
+                let value = self.values.pop().unwrap();
+                self.values.push(Value::from(shape.matches(&value)));
+            }
+            &Inst::Item(index) => {
+                let item = match self.pop_value() {
+                    Value::Compound(compound) => compound.values.get(usize::from(index)).cloned(),
+                    Value::Vector(values) => values.get(usize::from(index)).cloned(),
+                    _ => None,
+                };
+                let item = item.ok_or_else(|| anyhow!("no item at index {index}"))?;
+                self.values.push(item);
+            }
+            &Inst::Rest(index) => {
+                let values = self.pop_value().as_vector()?;
+                let rest: Vec<Value> = values.iter().skip(index.into()).cloned().collect();
+                self.values.push(Value::vector(rest));
+            }
+            Inst::Key(key) => {
+                let value = self.values.pop().unwrap().as_map()?.get(key).cloned();
+                let value = value.ok_or_else(|| anyhow!("no entry for key {key}"))?;
+                self.values.push(value);
+            }
+            Inst::CantDestructure(pattern) => {
+                let value = self.values.pop().unwrap();
+                return Err(anyhow!(
+                    "can't destructure {} with pattern {pattern}",
+                    Pretty::brief().render(&value)
+                ));
+            }
+            Inst::NoMatch => {
+                let value = self.pop_value();
+                return Err(anyhow!(
+                    "no `match` clause matches {}",
+                    Pretty::brief().render(&value)
+                ));
+            }
             &Inst::UnOp(op) => {
                 let a = self.pop_value();
                 let b = op.apply(&a)?;
@@ -71,6 +154,10 @@ impl VM {
                 locals[index_] = value;
             }
             &Inst::Jump(jmp_pc) => {
+                if jmp_pc < current_frame.pc {
+                    self.interrupt.check()?;
+                }
+
                 current_frame.pc = jmp_pc;
             }
             &Inst::JumpIf(jmp_pc) => {
@@ -86,15 +173,26 @@ impl VM {
                 }
             }
             &Inst::Call(arity) => {
+                self.interrupt.check()?;
                 let func = self.pop_value();
                 let new_frame = self.frame_from_func(&func, arity)?;
+
+                if let Some(tracer) = &mut self.tracer.0 {
+                    tracer.call(&func, arity, self.frames.len() + 2);
+                }
+
                 self.frames.push(current_frame.into());
                 return Ok(Some(new_frame));
             }
             Inst::Return => {
+                if let Some(tracer) = &mut self.tracer.0 {
+                    tracer.return_(current_frame.fn_id, self.frames.len() + 1);
+                }
+
                 return Ok(None);
             }
             &Inst::Recur(frame_index, jmp_pc) => {
+                self.interrupt.check()?;
                 let mut new_frame = current_frame;
 
                 for _ in 0..frame_index {
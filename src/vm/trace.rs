@@ -0,0 +1,124 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    io::Write,
+};
+
+use crate::{Error, FnId, Inst, Value};
+
+pub trait Tracer {
+    fn inst(&mut self, _fn_id: FnId, _pc: u32, _inst: &Inst, _depth: usize, _stack: usize) {}
+
+    fn call(&mut self, _func: &Value, _arity: u16, _depth: usize) {}
+
+    fn return_(&mut self, _fn_id: FnId, _depth: usize) {}
+
+    fn native(&mut self, _fn_id: FnId, _args: &[Value], _depth: usize) {}
+
+    fn error(&mut self, _error: &Error, _depth: usize) {}
+}
+
+#[derive(Default)]
+pub(super) struct Slot(pub Option<Box<dyn Tracer>>);
+
+impl Debug for Slot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.0.is_some() {
+            write!(f, "Some(..)")
+        } else {
+            write!(f, "None")
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct WriteTracer<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> WriteTracer<W> {
+    pub fn new(writer: W) -> Self {
+        WriteTracer { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+// write errors are deliberately ignored so tracing can never abort an evaluation
+impl<W: Write> Tracer for WriteTracer<W> {
+    fn inst(&mut self, fn_id: FnId, pc: u32, inst: &Inst, depth: usize, stack: usize) {
+        let _ = writeln!(
+            self.writer,
+            "inst fn={fn_id} pc={pc} depth={depth} stack={stack} inst={inst:?}"
+        );
+    }
+
+    fn call(&mut self, func: &Value, arity: u16, depth: usize) {
+        let _ = writeln!(self.writer, "call fn={func} arity={arity} depth={depth}");
+    }
+
+    fn return_(&mut self, fn_id: FnId, depth: usize) {
+        let _ = writeln!(self.writer, "return fn={fn_id} depth={depth}");
+    }
+
+    fn native(&mut self, fn_id: FnId, args: &[Value], depth: usize) {
+        let _ = writeln!(
+            self.writer,
+            "native fn={fn_id} args={} depth={depth}",
+            args.len()
+        );
+    }
+
+    fn error(&mut self, error: &Error, depth: usize) {
+        let _ = writeln!(
+            self.writer,
+            "error depth={depth} message={:?}",
+            error.to_string()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, io, rc::Rc};
+
+    use crate::{Module, VM, WriteTracer};
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_tracer() {
+        let buffer = SharedBuffer::default();
+        let mut vm = VM::new();
+        vm.set_tracer(Box::new(WriteTracer::new(buffer.clone())));
+        let mut module = Module::new(&mut vm);
+
+        module.eval_str("(list 1)").unwrap();
+        assert!(module.eval_str("($add 1 'x)").is_err());
+
+        let output = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("inst fn="));
+        assert!(lines[0].ends_with("pc=0 depth=1 stack=0 inst=Value(Integer(1))"));
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.starts_with("call fn=(#native-fn"))
+        );
+        assert!(lines.iter().any(|line| line.starts_with("native fn=")));
+        assert!(lines.iter().any(|line| line.starts_with("return fn=")));
+        assert!(lines.last().unwrap().starts_with("error depth=1 message="));
+    }
+}